@@ -1,10 +1,60 @@
-use std::collections::BTreeSet;
-use std::fs::read_dir;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, read_dir};
 use std::io;
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
 
 pub trait AbstractFilesystem {
     fn file_names_in(&self, rel_path: &str) -> io::Result<BTreeSet<Box<str>>>;
+
+    /// Contents of the file at `rel_path`.
+    ///
+    /// The default implementation fails with `io::ErrorKind::Unsupported`.
+    fn read_file(&self, rel_path: &str) -> io::Result<Vec<u8>> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("can't read {}: reading files is not supported", rel_path),
+        ))
+    }
+
+    /// Whether there is a file or directory at `rel_path`.
+    ///
+    /// The default implementation looks for the file name in its parent directory.
+    fn exists(&self, rel_path: &str) -> bool {
+        let path = Path::new(rel_path);
+        match (path.parent(), path.file_name()) {
+            (Some(parent), Some(file_name)) => {
+                let parent = parent.to_str().filter(|p| !p.is_empty()).unwrap_or(".");
+                self.file_names_in(parent)
+                    .is_ok_and(|names| names.contains(&*file_name.to_string_lossy()))
+            }
+            _ => self.is_dir(rel_path),
+        }
+    }
+
+    /// Whether `rel_path` is a directory.
+    ///
+    /// The default implementation checks whether `file_names_in` can list it.
+    fn is_dir(&self, rel_path: &str) -> bool {
+        self.file_names_in(rel_path).is_ok()
+    }
+}
+
+impl<T: AbstractFilesystem + ?Sized> AbstractFilesystem for &T {
+    fn file_names_in(&self, rel_path: &str) -> io::Result<BTreeSet<Box<str>>> {
+        (**self).file_names_in(rel_path)
+    }
+
+    fn read_file(&self, rel_path: &str) -> io::Result<Vec<u8>> {
+        (**self).read_file(rel_path)
+    }
+
+    fn exists(&self, rel_path: &str) -> bool {
+        (**self).exists(rel_path)
+    }
+
+    fn is_dir(&self, rel_path: &str) -> bool {
+        (**self).is_dir(rel_path)
+    }
 }
 
 pub struct Filesystem<'a> {
@@ -27,4 +77,196 @@ impl<'a> AbstractFilesystem for Filesystem<'a> {
             })
             .collect())
     }
+
+    fn read_file(&self, rel_path: &str) -> io::Result<Vec<u8>> {
+        fs::read(self.path.join(rel_path))
+    }
+
+    fn exists(&self, rel_path: &str) -> bool {
+        self.path.join(rel_path).exists()
+    }
+
+    fn is_dir(&self, rel_path: &str) -> bool {
+        self.path.join(rel_path).is_dir()
+    }
+}
+
+/// Files kept in memory, e.g. for tests, or for sources that are not on disk.
+///
+/// Directories are implied by the paths of the files in them, so there are no empty
+/// directories. Paths are relative and use `/` as the separator, e.g. `src/bin/cli.rs`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryFilesystem {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+impl MemoryFilesystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file, replacing any file at the same path.
+    ///
+    /// Panics if `path` is absolute or leads above the root, see `try_insert`.
+    pub fn with_file(mut self, path: impl AsRef<Path>, contents: impl Into<Vec<u8>>) -> Self {
+        if let Err(err) = self.try_insert(path, contents) {
+            panic!("{}", err);
+        }
+        self
+    }
+
+    /// Add a file, returning the previous contents of the file at the same path.
+    ///
+    /// Fails with `io::ErrorKind::InvalidInput` if `path` is absolute or leads above the root,
+    /// e.g. `../foo`.
+    pub fn try_insert(
+        &mut self,
+        path: impl AsRef<Path>,
+        contents: impl Into<Vec<u8>>,
+    ) -> io::Result<Option<Vec<u8>>> {
+        let path = path.as_ref();
+        let normalized = normalize(path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a path inside the filesystem", path.display()),
+            )
+        })?;
+        Ok(self.files.insert(normalized.into(), contents.into()))
+    }
+
+    /// Remove a file, returning its contents.
+    pub fn remove(&mut self, path: impl AsRef<Path>) -> Option<Vec<u8>> {
+        self.files.remove(Path::new(&normalize(path.as_ref())?))
+    }
+
+    /// `rel_path` normalized, or `NotFound` if it is outside of the filesystem.
+    fn path(&self, rel_path: &str) -> io::Result<PathBuf> {
+        normalize(Path::new(rel_path))
+            .map(PathBuf::from)
+            .ok_or_else(|| not_found(rel_path))
+    }
+
+    /// Paths and contents of all files.
+    pub fn files(&self) -> &BTreeMap<PathBuf, Vec<u8>> {
+        &self.files
+    }
+}
+
+/// Fails like `try_insert` if any of the paths is outside of the filesystem.
+impl TryFrom<BTreeMap<PathBuf, Vec<u8>>> for MemoryFilesystem {
+    type Error = io::Error;
+
+    fn try_from(files: BTreeMap<PathBuf, Vec<u8>>) -> io::Result<Self> {
+        let mut fs = Self::new();
+        for (path, contents) in files {
+            fs.try_insert(path, contents)?;
+        }
+        Ok(fs)
+    }
+}
+
+impl AbstractFilesystem for MemoryFilesystem {
+    fn file_names_in(&self, rel_path: &str) -> io::Result<BTreeSet<Box<str>>> {
+        let dir = self.path(rel_path)?;
+        if self.files.contains_key(&dir) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a directory", rel_path),
+            ));
+        }
+        let names: BTreeSet<Box<str>> = self
+            .files
+            .keys()
+            .filter_map(|path| path.strip_prefix(&dir).ok()?.components().next())
+            .map(|name| name.as_os_str().to_string_lossy().into())
+            .collect();
+        if names.is_empty() && dir.components().next().is_some() {
+            return Err(not_found(rel_path));
+        }
+        Ok(names)
+    }
+
+    fn read_file(&self, rel_path: &str) -> io::Result<Vec<u8>> {
+        self.files
+            .get(&self.path(rel_path)?)
+            .cloned()
+            .ok_or_else(|| not_found(rel_path))
+    }
+
+    fn exists(&self, rel_path: &str) -> bool {
+        self.path(rel_path)
+            .is_ok_and(|path| self.files.contains_key(&path) || self.is_dir(rel_path))
+    }
+
+    fn is_dir(&self, rel_path: &str) -> bool {
+        self.path(rel_path).is_ok_and(|dir| {
+            dir.components().next().is_none()
+                || self
+                    .files
+                    .keys()
+                    .any(|path| path != &dir && path.starts_with(&dir))
+        })
+    }
+}
+
+fn not_found(rel_path: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} does not exist", rel_path),
+    )
+}
+
+/// Resolve `.` and `..` in a relative path without touching the filesystem, using `/` as the
+/// separator. Returns `None` if the path is absolute or leads above its starting point.
+pub(crate) fn normalize(path: &Path) -> Option<String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy()),
+            Component::ParentDir => {
+                parts.pop()?;
+            }
+            Component::CurDir => {}
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// View of a subdirectory of another filesystem.
+pub(crate) struct SubFilesystem<'a> {
+    inner: &'a dyn AbstractFilesystem,
+    prefix: &'a str,
+}
+
+impl<'a> SubFilesystem<'a> {
+    pub(crate) fn new(inner: &'a dyn AbstractFilesystem, prefix: &'a str) -> Self {
+        Self { inner, prefix }
+    }
+
+    fn path(&self, rel_path: &str) -> String {
+        match (self.prefix, rel_path) {
+            ("", rel_path) => rel_path.to_string(),
+            (prefix, "." | "") => prefix.to_string(),
+            (prefix, rel_path) => format!("{}/{}", prefix, rel_path),
+        }
+    }
+}
+
+impl<'a> AbstractFilesystem for SubFilesystem<'a> {
+    fn file_names_in(&self, rel_path: &str) -> io::Result<BTreeSet<Box<str>>> {
+        self.inner.file_names_in(&self.path(rel_path))
+    }
+
+    fn read_file(&self, rel_path: &str) -> io::Result<Vec<u8>> {
+        self.inner.read_file(&self.path(rel_path))
+    }
+
+    fn exists(&self, rel_path: &str) -> bool {
+        self.inner.exists(&self.path(rel_path))
+    }
+
+    fn is_dir(&self, rel_path: &str) -> bool {
+        self.inner.is_dir(&self.path(rel_path))
+    }
 }
@@ -0,0 +1,465 @@
+use crate::{
+    DepKind, Dependency, DependencyDetail, DepsSet, Error, FeatureValue, Manifest, SourceKind,
+    Target, TargetDepsSet,
+};
+
+/// A dependency in one of the dependency tables of a manifest,
+/// see `Manifest::all_dependencies`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DependencyRef<'a> {
+    /// Name the dependency is declared under, which is also its name in code.
+    pub name: &'a str,
+    /// Name of the package on the registry; differs from `name` if the dependency is renamed
+    /// with `package = "..."`.
+    pub package_name: &'a str,
+    pub kind: DepKind,
+    /// Key of the `[target.'...']` table, if it is platform-specific.
+    pub platform: Option<&'a str>,
+    pub dep: &'a Dependency,
+}
+
+/// Mutable counterpart of `DependencyRef`, see `Manifest::all_dependencies_mut`.
+#[derive(Debug, PartialEq)]
+pub struct DependencyMut<'a> {
+    pub name: &'a str,
+    pub kind: DepKind,
+    pub platform: Option<&'a str>,
+    pub dep: &'a mut Dependency,
+}
+
+impl DependencyRef<'_> {
+    /// Dotted path of the dependency in the manifest, e.g. `dependencies.serde` or
+    /// `target.cfg(unix).dev-dependencies.libc`.
+    pub fn key(&self) -> String {
+        dependency_key(self.kind, self.platform, self.name)
+    }
+
+    /// `Dependency::semver_req`, with errors naming the dependency by its `key`.
+    #[cfg(feature = "semver")]
+    pub fn semver_req(&self) -> Result<semver::VersionReq, Error> {
+        self.dep.semver_req().map_err(|err| match err {
+            Error::Semver(what, err) => Error::Semver(format!("{} of `{}`", what, self.key()), err),
+            Error::InvalidDependency(msg) => {
+                Error::InvalidDependency(format!("`{}`: {}", self.key(), msg))
+            }
+            err => err,
+        })
+    }
+}
+
+impl DependencyMut<'_> {
+    /// See `DependencyRef::key`.
+    pub fn key(&self) -> String {
+        dependency_key(self.kind, self.platform, self.name)
+    }
+
+    /// See `DependencyRef::package_name`. It's a method here, because it borrows from `dep`,
+    /// which may be changed.
+    pub fn package_name(&self) -> &str {
+        self.dep.package().unwrap_or(self.name)
+    }
+}
+
+fn dependency_key(kind: DepKind, platform: Option<&str>, name: &str) -> String {
+    match platform {
+        Some(platform) => format!("target.{}.{}.{}", platform, kind.table_name(), name),
+        None => format!("{}.{}", kind.table_name(), name),
+    }
+}
+
+impl<Metadata> Manifest<Metadata> {
+    /// Every dependency, from `[dependencies]`, `[dev-dependencies]` and
+    /// `[build-dependencies]`, followed by those of each `[target.'...']` table.
+    pub fn all_dependencies(&self) -> impl Iterator<Item = DependencyRef<'_>> {
+        let tables = [
+            (DepKind::Normal, self.dependencies.as_ref()),
+            (DepKind::Dev, self.dev_dependencies.as_ref()),
+            (DepKind::Build, self.build_dependencies.as_ref()),
+        ];
+        let tables = tables
+            .into_iter()
+            .filter_map(|(kind, deps)| Some((kind, None, deps?)));
+        let target_tables = self.target.iter().flatten().flat_map(|(platform, target)| {
+            [
+                (
+                    DepKind::Normal,
+                    Some(platform.as_str()),
+                    &target.dependencies,
+                ),
+                (
+                    DepKind::Dev,
+                    Some(platform.as_str()),
+                    &target.dev_dependencies,
+                ),
+                (
+                    DepKind::Build,
+                    Some(platform.as_str()),
+                    &target.build_dependencies,
+                ),
+            ]
+        });
+        tables
+            .chain(target_tables)
+            .flat_map(|(kind, platform, deps)| {
+                deps.iter().map(move |(name, dep)| DependencyRef {
+                    name,
+                    package_name: dep.package().unwrap_or(name),
+                    kind,
+                    platform,
+                    dep,
+                })
+            })
+    }
+
+    /// Like `all_dependencies`, but allows changing the dependencies in place.
+    pub fn all_dependencies_mut(&mut self) -> impl Iterator<Item = DependencyMut<'_>> {
+        let tables = [
+            (DepKind::Normal, self.dependencies.as_mut()),
+            (DepKind::Dev, self.dev_dependencies.as_mut()),
+            (DepKind::Build, self.build_dependencies.as_mut()),
+        ];
+        let tables = tables
+            .into_iter()
+            .filter_map(|(kind, deps)| Some((kind, None, deps?)));
+        let target_tables = self
+            .target
+            .iter_mut()
+            .flatten()
+            .flat_map(|(platform, target)| {
+                let Target {
+                    dependencies,
+                    dev_dependencies,
+                    build_dependencies,
+                } = target;
+                [
+                    (DepKind::Normal, Some(platform.as_str()), dependencies),
+                    (DepKind::Dev, Some(platform.as_str()), dev_dependencies),
+                    (DepKind::Build, Some(platform.as_str()), build_dependencies),
+                ]
+            });
+        tables
+            .chain(target_tables)
+            .flat_map(|(kind, platform, deps): (_, _, &mut DepsSet)| {
+                deps.iter_mut().map(move |(name, dep)| DependencyMut {
+                    name,
+                    kind,
+                    platform,
+                    dep,
+                })
+            })
+    }
+}
+
+/// Index URLs of crates.io, as used in package ID specs.
+const CRATES_IO_INDEXES: &[&str] = &[
+    "https://github.com/rust-lang/crates.io-index",
+    "https://index.crates.io",
+];
+
+impl<Metadata> Manifest<Metadata> {
+    /// Add a dependency like `cargo add`, to the table for `kind`, or to the one of the
+    /// `[target.'...']` table for `platform`.
+    ///
+    /// If the table already has a dependency `name`, `dependency` is merged into it: its
+    /// `features` are added to the existing ones, a new source (`path`, `git` or a registry)
+    /// replaces the old one, and other keys set in `dependency` replace existing values.
+    /// A dependency with nothing but a version is written as `name = "version"`.
+    /// `ManifestDocument::add_dependency` does the same, keeping the formatting.
+    pub fn add_dependency(
+        &mut self,
+        kind: DepKind,
+        platform: Option<&str>,
+        name: &str,
+        dependency: &Dependency,
+    ) {
+        let table = self.dependency_table_mut(kind, platform);
+        let dep = merge_dependency(table.remove(name), dependency);
+        table.insert(name.to_string(), dep);
+    }
+
+    /// Remove a dependency like `cargo remove`, returning it if it existed.
+    ///
+    /// If an optional dependency is removed and no other table declares an optional
+    /// dependency of that name, references to it (`dep:name`, `name/feature`, `name?/feature`
+    /// and its implicit feature `name`) are removed from `[features]`. If the table becomes
+    /// empty, it is removed, and so is a `[target.'...']` table that has no other dependencies.
+    pub fn remove_dependency(
+        &mut self,
+        kind: DepKind,
+        platform: Option<&str>,
+        name: &str,
+    ) -> Option<Dependency> {
+        let removed = self.remove_from_dependency_table(kind, platform, name);
+
+        let was_optional = removed.as_ref().is_some_and(Dependency::optional);
+        let still_optional = self
+            .all_dependencies()
+            .any(|entry| entry.name == name && entry.dep.optional());
+        if let (true, false, Some(features)) = (was_optional, still_optional, &mut self.features) {
+            let explicit_feature = features.contains_key(name);
+            for values in features.values_mut() {
+                values
+                    .retain(|value| !feature_references_dependency(value, name, explicit_feature));
+            }
+        }
+        removed
+    }
+
+    /// Set the version requirement of an existing dependency, like `cargo upgrade`.
+    ///
+    /// Other keys, e.g. the `path` of a dependency that also has a version, are kept.
+    /// Returns `false` if there is no such dependency. Fails for a dependency inherited from
+    /// the workspace, whose version can only be changed in the workspace root.
+    pub fn set_dependency_version(
+        &mut self,
+        kind: DepKind,
+        platform: Option<&str>,
+        name: &str,
+        version: &str,
+    ) -> Result<bool, Error> {
+        let existing = self
+            .dependency_table(kind, platform)
+            .and_then(|table| table.get(name));
+        let dep = match existing {
+            Some(existing) => with_version(existing, name, version)?,
+            None => return Ok(false),
+        };
+        self.dependency_table_mut(kind, platform)
+            .insert(name.to_string(), dep);
+        Ok(true)
+    }
+
+    /// Move the entries of the deprecated `[replace]` table to `[patch]`.
+    ///
+    /// A replacement of `name@version` from the source `url` becomes `[patch.<url>] name`,
+    /// or `[patch.crates-io] name` for crates.io. Unlike `[replace]`, a patch is only used
+    /// if its version matches the dependency's requirements.
+    /// An empty `[replace]` is removed without adding `[patch]`.
+    /// Fails, leaving the manifest unchanged, if `[patch]` already has an entry for the package.
+    pub fn migrate_replace_to_patch(&mut self) -> Result<(), Error> {
+        let replace = match self.replace {
+            Some(ref replace) if !replace.is_empty() => replace,
+            _ => {
+                self.replace = None;
+                return Ok(());
+            }
+        };
+        let mut patch = self.patch.clone().unwrap_or_default();
+        for (spec, dep) in replace {
+            let source = match (&spec.url, &spec.kind) {
+                (None, _) => "crates-io".to_string(),
+                (Some(url), _) if CRATES_IO_INDEXES.contains(&url.trim_end_matches('/')) => {
+                    "crates-io".to_string()
+                }
+                (Some(url), Some(SourceKind::SparseRegistry)) => format!("sparse+{}", url),
+                (Some(url), _) => url.clone(),
+            };
+            let deps = patch.entry(source.clone()).or_default();
+            if deps.contains_key(&spec.name) {
+                return Err(Error::InvalidDependency(format!(
+                    "cannot move `replace.\"{}\"` to `patch.{}.{}`, which already exists",
+                    spec.as_written(),
+                    source,
+                    spec.name
+                )));
+            }
+            deps.insert(spec.name.clone(), dep.clone());
+        }
+        self.replace = None;
+        self.patch = Some(patch);
+        Ok(())
+    }
+
+    /// The table for `kind`, or the one of the `[target.'...']` table for `platform`.
+    pub(crate) fn dependency_table(
+        &self,
+        kind: DepKind,
+        platform: Option<&str>,
+    ) -> Option<&DepsSet> {
+        let target = match platform {
+            None => {
+                return match kind {
+                    DepKind::Normal => self.dependencies.as_ref(),
+                    DepKind::Dev => self.dev_dependencies.as_ref(),
+                    DepKind::Build => self.build_dependencies.as_ref(),
+                }
+            }
+            Some(platform) => self.target.as_ref()?.get(platform)?,
+        };
+        Some(match kind {
+            DepKind::Normal => &target.dependencies,
+            DepKind::Dev => &target.dev_dependencies,
+            DepKind::Build => &target.build_dependencies,
+        })
+    }
+
+    fn dependency_table_mut(&mut self, kind: DepKind, platform: Option<&str>) -> &mut DepsSet {
+        let target = match platform {
+            None => {
+                let table = match kind {
+                    DepKind::Normal => &mut self.dependencies,
+                    DepKind::Dev => &mut self.dev_dependencies,
+                    DepKind::Build => &mut self.build_dependencies,
+                };
+                return table.get_or_insert_with(DepsSet::new);
+            }
+            Some(platform) => self
+                .target
+                .get_or_insert_with(TargetDepsSet::new)
+                .entry(platform.to_string())
+                .or_default(),
+        };
+        match kind {
+            DepKind::Normal => &mut target.dependencies,
+            DepKind::Dev => &mut target.dev_dependencies,
+            DepKind::Build => &mut target.build_dependencies,
+        }
+    }
+
+    /// Remove `name` from an existing table, and the table itself if that leaves it empty.
+    fn remove_from_dependency_table(
+        &mut self,
+        kind: DepKind,
+        platform: Option<&str>,
+        name: &str,
+    ) -> Option<Dependency> {
+        let platform = match platform {
+            None => {
+                let table = match kind {
+                    DepKind::Normal => &mut self.dependencies,
+                    DepKind::Dev => &mut self.dev_dependencies,
+                    DepKind::Build => &mut self.build_dependencies,
+                };
+                let removed = table.as_mut()?.remove(name)?;
+                if table.as_ref().is_some_and(DepsSet::is_empty) {
+                    *table = None;
+                }
+                return Some(removed);
+            }
+            Some(platform) => platform,
+        };
+        let targets = self.target.as_mut()?;
+        let target = targets.get_mut(platform)?;
+        let removed = match kind {
+            DepKind::Normal => target.dependencies.remove(name),
+            DepKind::Dev => target.dev_dependencies.remove(name),
+            DepKind::Build => target.build_dependencies.remove(name),
+        }?;
+        if target.dependencies.is_empty()
+            && target.dev_dependencies.is_empty()
+            && target.build_dependencies.is_empty()
+        {
+            targets.remove(platform);
+            if targets.is_empty() {
+                self.target = None;
+            }
+        }
+        Some(removed)
+    }
+}
+
+/// `dependency` merged into `existing`, as described in `Manifest::add_dependency`.
+pub(crate) fn merge_dependency(
+    existing: Option<Dependency>,
+    dependency: &Dependency,
+) -> Dependency {
+    let dep = match existing {
+        Some(existing) => merge(into_detail(existing), into_detail(dependency.clone())),
+        None => into_detail(dependency.clone()),
+    };
+    simplify(dep)
+}
+
+/// `dependency` with its version requirement replaced, see `Manifest::set_dependency_version`.
+pub(crate) fn with_version(
+    dependency: &Dependency,
+    name: &str,
+    version: &str,
+) -> Result<Dependency, Error> {
+    match dependency {
+        Dependency::Simple(_) => Ok(Dependency::Simple(version.to_string())),
+        Dependency::Detailed(detail) if detail.workspace == Some(true) => {
+            Err(Error::InvalidDependency(format!(
+                "can't set the version of `{}`, which is inherited from the workspace",
+                name
+            )))
+        }
+        Dependency::Detailed(detail) => Ok(Dependency::Detailed(DependencyDetail {
+            version: Some(version.to_string()),
+            ..detail.clone()
+        })),
+    }
+}
+
+/// Whether the `[features]` entry `value` refers to the optional dependency `name`.
+/// A plain `name` refers to it only if there is no explicit feature of that name.
+pub(crate) fn feature_references_dependency(
+    value: &str,
+    name: &str,
+    explicit_feature: bool,
+) -> bool {
+    match FeatureValue::parse(value) {
+        FeatureValue::Dep(dep) | FeatureValue::DepFeature { dep, .. } => dep == name,
+        FeatureValue::Feature(feature) => !explicit_feature && feature == name,
+    }
+}
+
+fn into_detail(dep: Dependency) -> DependencyDetail {
+    match dep {
+        Dependency::Simple(version) => DependencyDetail {
+            version: Some(version),
+            ..DependencyDetail::default()
+        },
+        Dependency::Detailed(detail) => detail,
+    }
+}
+
+/// `{ version = "1" }` as `"1"`, everything else as is.
+pub(crate) fn simplify(detail: DependencyDetail) -> Dependency {
+    let version_only = DependencyDetail {
+        version: detail.version.clone(),
+        ..DependencyDetail::default()
+    };
+    match detail.version {
+        Some(ref version) if detail == version_only => Dependency::Simple(version.clone()),
+        _ => Dependency::Detailed(detail),
+    }
+}
+
+fn merge(mut existing: DependencyDetail, spec: DependencyDetail) -> DependencyDetail {
+    fn set<T>(field: &mut Option<T>, value: Option<T>) {
+        if value.is_some() {
+            *field = value;
+        }
+    }
+    let has_source = spec.path.is_some()
+        || spec.git.is_some()
+        || spec.registry.is_some()
+        || spec.registry_index.is_some();
+    if has_source {
+        existing.path = spec.path;
+        existing.git = spec.git;
+        existing.branch = spec.branch;
+        existing.tag = spec.tag;
+        existing.rev = spec.rev;
+        existing.registry = spec.registry;
+        existing.registry_index = spec.registry_index;
+    }
+    if let Some(features) = spec.features {
+        let merged = existing.features.get_or_insert_with(Vec::new);
+        for feature in features {
+            if !merged.contains(&feature) {
+                merged.push(feature);
+            }
+        }
+    }
+    set(&mut existing.version, spec.version);
+    set(&mut existing.optional, spec.optional);
+    set(&mut existing.workspace, spec.workspace);
+    set(&mut existing.default_features, spec.default_features);
+    set(&mut existing.package, spec.package);
+    set(&mut existing.artifact, spec.artifact);
+    set(&mut existing.target, spec.target);
+    set(&mut existing.lib, spec.lib);
+    existing
+}
@@ -0,0 +1,457 @@
+use crate::dependencies::{feature_references_dependency, merge_dependency, with_version};
+use crate::{DepKind, Dependency, Error, Manifest, Value};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use toml_edit::ser::ValueSerializer;
+use toml_edit::{Document, InlineTable, Item, Table, TableLike};
+
+/// A `Cargo.toml` file that can be edited without reformatting it.
+///
+/// Mutations are written into the underlying `toml_edit::Document`, so comments, key order
+/// and inline-table styling of everything that isn't edited are kept as they were.
+/// The typed `Manifest` is re-parsed after every mutation, and a mutation that would make
+/// the manifest invalid is rejected and leaves the document unchanged.
+///
+/// Use `to_string()` to get the edited `Cargo.toml` contents.
+#[derive(Debug, Clone)]
+pub struct ManifestDocument<Metadata = Value> {
+    document: Document,
+    manifest: Manifest<Metadata>,
+}
+
+impl FromStr for ManifestDocument<Value> {
+    type Err = Error;
+
+    /// Parse contents of a `Cargo.toml` file loaded as a string
+    fn from_str(cargo_toml_content: &str) -> Result<Self, Self::Err> {
+        Self::from_str_with_metadata(cargo_toml_content)
+    }
+}
+
+impl<Metadata: for<'a> Deserialize<'a>> ManifestDocument<Metadata> {
+    /// Parse `Cargo.toml`, and parse its `[package.metadata]` into a custom Serde-compatible type.
+    pub fn from_str_with_metadata(cargo_toml_content: &str) -> Result<Self, Error> {
+        Ok(Self {
+            document: cargo_toml_content.parse()?,
+            manifest: Manifest::from_slice_with_metadata(cargo_toml_content.as_bytes())?,
+        })
+    }
+
+    /// The typed view of the current contents.
+    pub fn manifest(&self) -> &Manifest<Metadata> {
+        &self.manifest
+    }
+
+    /// The format-preserving view of the current contents.
+    pub fn document(&self) -> &Document {
+        &self.document
+    }
+
+    /// Set `package.version`.
+    ///
+    /// Returns `false` if there is no `[package]` table.
+    pub fn set_version(&mut self, version: &str) -> Result<bool, Error> {
+        self.edit(|document| match document.get_mut("package") {
+            Some(Item::Table(package)) => {
+                set_value(
+                    package.entry("version").or_insert(Item::None),
+                    version.into(),
+                );
+                true
+            }
+            _ => false,
+        })
+    }
+
+    /// Add a dependency to the table for `kind`, or to the one of the `[target.'...']` table
+    /// for `platform`, merging it with an existing one like `Manifest::add_dependency`.
+    ///
+    /// A changed entry keeps its position and style (`[dependencies.foo]` tables stay tables).
+    /// Fails if the dependency table exists, but is neither a table nor an inline table.
+    pub fn add_dependency(
+        &mut self,
+        kind: DepKind,
+        platform: Option<&str>,
+        name: &str,
+        dependency: &Dependency,
+    ) -> Result<(), Error> {
+        let existing = self
+            .manifest
+            .dependency_table(kind, platform)
+            .and_then(|table| table.get(name))
+            .cloned();
+        let value = match merge_dependency(existing, dependency) {
+            Dependency::Simple(version) => version.as_str().into(),
+            Dependency::Detailed(detail) => detail.serialize(ValueSerializer::new())?,
+        };
+        self.try_edit(|document| {
+            let not_a_table = |key: &str| {
+                Error::InvalidDependency(format!(
+                    "can't add `{}` to `{}`, which is not a table",
+                    name, key
+                ))
+            };
+            let parent = match platform {
+                None => document.as_table_mut(),
+                Some(platform) => document
+                    .entry("target")
+                    .or_insert_with(implicit_table)
+                    .as_table_mut()
+                    .ok_or_else(|| not_a_table("target"))?
+                    .entry(platform)
+                    .or_insert_with(implicit_table)
+                    .as_table_mut()
+                    .ok_or_else(|| not_a_table(&format!("target.{}", platform)))?,
+            };
+            let key = dependency_table_key(parent, kind);
+            match parent.entry(key).or_insert_with(toml_edit::table) {
+                Item::Table(table) => insert_dependency(table, name, value),
+                Item::Value(toml_edit::Value::InlineTable(table)) => {
+                    set_inline_value(table, name, value)
+                }
+                _ => return Err(not_a_table(key)),
+            }
+            Ok(())
+        })
+    }
+
+    /// Remove a dependency like `Manifest::remove_dependency`, returning it if it existed.
+    ///
+    /// References to a removed optional dependency are removed from `[features]`, and so is
+    /// a dependency table that becomes empty.
+    pub fn remove_dependency(
+        &mut self,
+        kind: DepKind,
+        platform: Option<&str>,
+        name: &str,
+    ) -> Result<Option<Dependency>, Error> {
+        let removed = match self
+            .manifest
+            .dependency_table(kind, platform)
+            .and_then(|table| table.get(name))
+        {
+            Some(removed) => removed.clone(),
+            None => return Ok(None),
+        };
+        let still_optional = self.manifest.all_dependencies().any(|entry| {
+            entry.name == name
+                && entry.dep.optional()
+                && (entry.kind, entry.platform) != (kind, platform)
+        });
+        let clean_features = removed.optional() && !still_optional;
+        self.edit(|document| {
+            if let Some(parent) = dependency_table_parent(document, platform) {
+                let key = dependency_table_key(parent, kind);
+                let table = parent.get_mut(key).and_then(Item::as_table_like_mut);
+                if let Some(table) = table {
+                    table.remove(name);
+                    if table.is_empty() {
+                        parent.remove(key);
+                    }
+                }
+            }
+            if let Some(platform) = platform {
+                let targets = document.get_mut("target").and_then(Item::as_table_like_mut);
+                if let Some(targets) = targets {
+                    if targets
+                        .get(platform)
+                        .and_then(Item::as_table_like)
+                        .is_some_and(TableLike::is_empty)
+                    {
+                        targets.remove(platform);
+                    }
+                    if targets.is_empty() {
+                        document.remove("target");
+                    }
+                }
+            }
+            if clean_features {
+                remove_feature_references(document, name);
+            }
+        })?;
+        Ok(Some(removed))
+    }
+
+    /// Set the version requirement of an existing dependency, like
+    /// `Manifest::set_dependency_version`.
+    ///
+    /// Only the version is changed, so comments and the style of the entry are kept.
+    pub fn set_dependency_version(
+        &mut self,
+        kind: DepKind,
+        platform: Option<&str>,
+        name: &str,
+        version: &str,
+    ) -> Result<bool, Error> {
+        match self
+            .manifest
+            .dependency_table(kind, platform)
+            .and_then(|table| table.get(name))
+        {
+            Some(existing) => with_version(existing, name, version)?,
+            None => return Ok(false),
+        };
+        self.edit(|document| {
+            let item = match dependency_item_mut(document, kind, platform, name) {
+                Some(item) => item,
+                None => return false,
+            };
+            match item {
+                Item::Value(toml_edit::Value::String(_)) => set_value(item, version.into()),
+                Item::Value(toml_edit::Value::InlineTable(table)) => {
+                    set_inline_value(table, "version", version.into())
+                }
+                Item::Table(table) => {
+                    set_value(table.entry("version").or_insert(Item::None), version.into())
+                }
+                _ => return false,
+            }
+            true
+        })
+    }
+
+    /// Enable or disable a feature of a dependency, by adding it to or removing it from
+    /// the dependency's `features` list.
+    ///
+    /// A dependency that is only a version requirement, e.g. `foo = "1.0"`, is turned into
+    /// an inline table when a feature is enabled. Returns `false` if nothing had to change,
+    /// or if there is no such dependency.
+    pub fn set_dependency_feature(
+        &mut self,
+        kind: DepKind,
+        platform: Option<&str>,
+        name: &str,
+        feature: &str,
+        enabled: bool,
+    ) -> Result<bool, Error> {
+        self.edit(|document| {
+            let item = match dependency_item_mut(document, kind, platform, name) {
+                Some(item) => item,
+                None => return false,
+            };
+            if let Some(version) = item.as_str() {
+                if !enabled {
+                    return false;
+                }
+                let mut inline = InlineTable::new();
+                inline.insert("version", version.into());
+                inline.insert("features", toml_edit::Array::from_iter([feature]).into());
+                set_value(item, inline.into());
+                return true;
+            }
+            set_feature(item, feature, enabled)
+        })
+    }
+
+    /// Apply `f` to a copy of the document, and keep the result only if it is still
+    /// a valid manifest.
+    fn edit<R>(&mut self, f: impl FnOnce(&mut Document) -> R) -> Result<R, Error> {
+        self.try_edit(|document| Ok(f(document)))
+    }
+
+    /// Like `edit`, but the document is also left unchanged if `f` fails.
+    fn try_edit<R>(
+        &mut self,
+        f: impl FnOnce(&mut Document) -> Result<R, Error>,
+    ) -> Result<R, Error> {
+        let mut document = self.document.clone();
+        let result = f(&mut document)?;
+        self.manifest = Manifest::from_slice_with_metadata(document.to_string().as_bytes())?;
+        self.document = document;
+        Ok(result)
+    }
+}
+
+impl<Metadata> fmt::Display for ManifestDocument<Metadata> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.document.fmt(f)
+    }
+}
+
+/// Existing spelling of the table for `kind` in `parent` (`dev_dependencies` is accepted too),
+/// or the kebab-case name if there is none yet.
+fn dependency_table_key(parent: &dyn TableLike, kind: DepKind) -> &'static str {
+    let alias = match kind {
+        DepKind::Normal => return "dependencies",
+        DepKind::Dev => "dev_dependencies",
+        DepKind::Build => "build_dependencies",
+    };
+    if !parent.contains_key(kind.table_name()) && parent.contains_key(alias) {
+        alias
+    } else {
+        kind.table_name()
+    }
+}
+
+/// The document itself, or the existing `[target.'...']` table for `platform`.
+fn dependency_table_parent<'a>(
+    document: &'a mut Document,
+    platform: Option<&str>,
+) -> Option<&'a mut dyn TableLike> {
+    match platform {
+        None => Some(document.as_table_mut()),
+        Some(platform) => document
+            .get_mut("target")?
+            .as_table_like_mut()?
+            .get_mut(platform)?
+            .as_table_like_mut(),
+    }
+}
+
+fn dependency_item_mut<'a>(
+    document: &'a mut Document,
+    kind: DepKind,
+    platform: Option<&str>,
+    name: &str,
+) -> Option<&'a mut Item> {
+    let parent = dependency_table_parent(document, platform)?;
+    let key = dependency_table_key(parent, kind);
+    parent.get_mut(key)?.as_table_like_mut()?.get_mut(name)
+}
+
+fn implicit_table() -> Item {
+    let mut table = Table::new();
+    table.set_implicit(true);
+    Item::Table(table)
+}
+
+/// Insert `value` into a `[dependencies]`-like table, replacing an existing entry in place.
+fn insert_dependency(table: &mut Table, name: &str, value: toml_edit::Value) {
+    match (table.get_mut(name), value) {
+        (Some(item @ Item::Table(_)), toml_edit::Value::InlineTable(inline)) => {
+            let position = item.as_table().and_then(Table::position);
+            let mut new_table = inline.into_table();
+            if let Some(position) = position {
+                new_table.set_position(position);
+            }
+            *item = Item::Table(new_table);
+        }
+        (Some(item), value) => set_value(item, value),
+        (None, value) => {
+            table.insert(name, Item::Value(value));
+        }
+    }
+}
+
+/// Add `feature` to or remove it from the `features` of a dependency table. An inline table
+/// that is left with nothing but a version is written as `name = "version"`.
+fn set_feature(item: &mut Item, feature: &str, enabled: bool) -> bool {
+    let table = match item.as_table_like_mut() {
+        Some(table) => table,
+        None => return false,
+    };
+    let features = table
+        .entry("features")
+        .or_insert_with(|| toml_edit::value(toml_edit::Array::new()));
+    let features = match features.as_array_mut() {
+        Some(features) => features,
+        None => return false,
+    };
+    let position = features.iter().position(|f| f.as_str() == Some(feature));
+    let changed = match (position, enabled) {
+        (None, true) => {
+            features.push(feature);
+            true
+        }
+        (Some(index), false) => {
+            remove_array_element(features, index);
+            true
+        }
+        _ => false,
+    };
+    if !features.is_empty() {
+        return changed;
+    }
+    match item {
+        Item::Value(toml_edit::Value::InlineTable(table)) => {
+            remove_inline_value(table, "features");
+            let version = match table.get("version").and_then(toml_edit::Value::as_str) {
+                Some(version) if changed && table.len() == 1 => version.to_string(),
+                _ => return changed,
+            };
+            set_value(item, version.as_str().into());
+        }
+        item => {
+            if let Some(table) = item.as_table_like_mut() {
+                table.remove("features");
+            }
+        }
+    }
+    changed
+}
+
+/// Remove `name` from the lists in `[features]`, see `feature_references_dependency`.
+fn remove_feature_references(document: &mut Document, name: &str) {
+    let features = match document
+        .get_mut("features")
+        .and_then(Item::as_table_like_mut)
+    {
+        Some(features) => features,
+        None => return,
+    };
+    let explicit_feature = features.contains_key(name);
+    for (_, values) in features.iter_mut() {
+        let values = match values.as_array_mut() {
+            Some(values) => values,
+            None => continue,
+        };
+        for index in (0..values.len()).rev() {
+            let references = values
+                .get(index)
+                .and_then(toml_edit::Value::as_str)
+                .is_some_and(|value| feature_references_dependency(value, name, explicit_feature));
+            if references {
+                remove_array_element(values, index);
+            }
+        }
+    }
+}
+
+fn remove_array_element(array: &mut toml_edit::Array, index: usize) {
+    let removed = array.remove(index);
+    // The next element takes the place of the removed one, including its leading whitespace.
+    if let (Some(next), Some(prefix)) = (array.get_mut(index), removed.decor().prefix()) {
+        next.decor_mut().set_prefix(prefix.clone());
+    }
+}
+
+/// Remove `key` from an inline table, keeping the space before `}` if it was the last value.
+fn remove_inline_value(table: &mut InlineTable, key: &str) {
+    let was_last = table.iter().last().is_some_and(|(last, _)| last == key);
+    let removed = match table.remove(key) {
+        Some(removed) => removed,
+        None => return,
+    };
+    if let (true, Some((_, last)), Some(suffix)) =
+        (was_last, table.iter_mut().last(), removed.decor().suffix())
+    {
+        last.decor_mut().set_suffix(suffix.clone());
+    }
+}
+
+/// Set `key` of an inline table, keeping the comments and whitespace around an old value.
+fn set_inline_value(table: &mut InlineTable, key: &str, value: toml_edit::Value) {
+    match table.get_mut(key) {
+        Some(item) => {
+            let decor = item.decor().clone();
+            *item = value;
+            *item.decor_mut() = decor;
+        }
+        None => {
+            // The space before `}` belongs to the last value, which is no longer last
+            if let Some((_, last)) = table.iter_mut().last() {
+                last.decor_mut().set_suffix("");
+            }
+            table.insert(key, value);
+        }
+    }
+}
+
+/// Replace the value of `item`, keeping the comments and whitespace around the old one.
+fn set_value(item: &mut Item, mut value: toml_edit::Value) {
+    if let Some(old) = item.as_value() {
+        *value.decor_mut() = old.decor().clone();
+    }
+    *item = Item::Value(value);
+}
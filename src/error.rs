@@ -1,20 +1,61 @@
 use std::error::Error as StdErr;
 use std::fmt;
 use std::io;
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub enum Error {
     Parse(toml::de::Error),
+    /// The document could not be parsed for format-preserving editing.
+    Edit(toml_edit::TomlError),
+    /// A value could not be converted to TOML.
+    Serialize(toml_edit::ser::Error),
     Io(io::Error),
     Utf8(std::str::Utf8Error),
+    /// A field is set to `{ workspace = true }`, but the workspace root does not define it.
+    /// Holds the name of the inherited key.
+    InheritedUnknownValue(String),
+    /// The workspace root could not be found or is not a valid workspace.
+    WorkspaceIntegrity(String),
+    /// A dependency specification is invalid or conflicts with the workspace.
+    InvalidDependency(String),
+    /// `[features]` refers to a feature or dependency that doesn't exist.
+    InvalidFeature(String),
+    /// Features that (transitively) include themselves, e.g. `["a", "b", "a"]`.
+    FeatureCycle(Vec<String>),
+    /// A `[target]` key is neither a target triple nor a valid `cfg(...)` expression.
+    InvalidPlatform(String),
+    /// An `edition` is not a year.
+    InvalidEdition(String),
+    /// A profile is missing, or profiles inherit from each other in a cycle.
+    InvalidProfile(String),
+    /// A package ID specification, e.g. a `[replace]` key, could not be parsed.
+    InvalidPackageIdSpec(String),
+    /// A version or version requirement is not valid semver.
+    /// Holds a description of what was parsed, e.g. the dependency name, and the
+    /// `semver::Error`. Only returned with the `semver` feature, but always defined, so that
+    /// enabling the feature doesn't break exhaustive matches.
+    Semver(String, Arc<dyn StdErr + Send + Sync>),
 }
 
 impl StdErr for Error {
     fn source(&self) -> Option<&(dyn StdErr + 'static)> {
         match *self {
             Error::Parse(ref err) => Some(err),
+            Error::Edit(ref err) => Some(err),
+            Error::Serialize(ref err) => Some(err),
             Error::Io(ref err) => Some(err),
             Error::Utf8(ref err) => Some(err),
+            Error::Semver(_, ref err) => Some(&**err),
+            Error::InheritedUnknownValue(_)
+            | Error::WorkspaceIntegrity(_)
+            | Error::InvalidDependency(_)
+            | Error::InvalidFeature(_)
+            | Error::FeatureCycle(_)
+            | Error::InvalidPlatform(_)
+            | Error::InvalidEdition(_)
+            | Error::InvalidProfile(_)
+            | Error::InvalidPackageIdSpec(_) => None,
         }
     }
 }
@@ -23,8 +64,26 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
             Error::Parse(ref err) => err.fmt(f),
+            Error::Edit(ref err) => err.fmt(f),
+            Error::Serialize(ref err) => err.fmt(f),
             Error::Io(ref err) => err.fmt(f),
             Error::Utf8(ref err) => err.fmt(f),
+            Error::InheritedUnknownValue(ref key) => write!(
+                f,
+                "`{}` is inherited from the workspace, but the workspace root does not define it",
+                key
+            ),
+            Error::WorkspaceIntegrity(ref msg) => f.write_str(msg),
+            Error::InvalidDependency(ref msg) => f.write_str(msg),
+            Error::InvalidFeature(ref msg) => f.write_str(msg),
+            Error::FeatureCycle(ref cycle) => {
+                write!(f, "cyclic feature dependency: {}", cycle.join(" -> "))
+            }
+            Error::InvalidPlatform(ref msg) => f.write_str(msg),
+            Error::InvalidEdition(ref msg) => f.write_str(msg),
+            Error::InvalidProfile(ref msg) => f.write_str(msg),
+            Error::InvalidPackageIdSpec(ref msg) => f.write_str(msg),
+            Error::Semver(ref what, ref err) => write!(f, "{}: {}", what, err),
         }
     }
 }
@@ -33,8 +92,20 @@ impl Clone for Error {
     fn clone(&self) -> Self {
         match *self {
             Error::Parse(ref err) => Error::Parse(err.clone()),
+            Error::Edit(ref err) => Error::Edit(err.clone()),
+            Error::Serialize(ref err) => Error::Serialize(err.clone()),
             Error::Io(ref err) => Error::Io(io::Error::new(err.kind(), err.to_string())),
             Error::Utf8(ref err) => Error::Utf8(*err),
+            Error::InheritedUnknownValue(ref key) => Error::InheritedUnknownValue(key.clone()),
+            Error::WorkspaceIntegrity(ref msg) => Error::WorkspaceIntegrity(msg.clone()),
+            Error::InvalidDependency(ref msg) => Error::InvalidDependency(msg.clone()),
+            Error::InvalidFeature(ref msg) => Error::InvalidFeature(msg.clone()),
+            Error::FeatureCycle(ref cycle) => Error::FeatureCycle(cycle.clone()),
+            Error::InvalidPlatform(ref msg) => Error::InvalidPlatform(msg.clone()),
+            Error::InvalidEdition(ref msg) => Error::InvalidEdition(msg.clone()),
+            Error::InvalidProfile(ref msg) => Error::InvalidProfile(msg.clone()),
+            Error::InvalidPackageIdSpec(ref msg) => Error::InvalidPackageIdSpec(msg.clone()),
+            Error::Semver(ref what, ref err) => Error::Semver(what.clone(), err.clone()),
         }
     }
 }
@@ -45,6 +116,18 @@ impl From<toml::de::Error> for Error {
     }
 }
 
+impl From<toml_edit::TomlError> for Error {
+    fn from(o: toml_edit::TomlError) -> Self {
+        Error::Edit(o)
+    }
+}
+
+impl From<toml_edit::ser::Error> for Error {
+    fn from(o: toml_edit::ser::Error) -> Self {
+        Error::Serialize(o)
+    }
+}
+
 impl From<io::Error> for Error {
     fn from(o: io::Error) -> Self {
         Error::Io(o)
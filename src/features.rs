@@ -0,0 +1,273 @@
+use crate::{DepKind, Error, Manifest};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// One entry of a feature's list in `[features]`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FeatureValue {
+    /// `name`: another feature of this package.
+    Feature(String),
+    /// `dep:name`: an optional dependency, without enabling the feature of the same name.
+    Dep(String),
+    /// `name/feature` enables a feature of a dependency, and the dependency itself if it's
+    /// optional. `name?/feature` (`weak`) only enables the feature if the dependency
+    /// is enabled by something else.
+    DepFeature {
+        dep: String,
+        feature: String,
+        weak: bool,
+    },
+}
+
+impl FeatureValue {
+    pub fn parse(value: &str) -> Self {
+        if let Some(dep) = value.strip_prefix("dep:") {
+            return FeatureValue::Dep(dep.to_string());
+        }
+        match value.split_once('/') {
+            Some((dep, feature)) => {
+                let (dep, weak) = match dep.strip_suffix('?') {
+                    Some(dep) => (dep, true),
+                    None => (dep, false),
+                };
+                FeatureValue::DepFeature {
+                    dep: dep.to_string(),
+                    feature: feature.to_string(),
+                    weak,
+                }
+            }
+            None => FeatureValue::Feature(value.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for FeatureValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeatureValue::Feature(name) => f.write_str(name),
+            FeatureValue::Dep(dep) => write!(f, "dep:{}", dep),
+            FeatureValue::DepFeature { dep, feature, weak } => {
+                write!(f, "{}{}/{}", dep, if *weak { "?" } else { "" }, feature)
+            }
+        }
+    }
+}
+
+/// The features of a package, including the implicit features of optional dependencies.
+///
+/// See https://doc.rust-lang.org/cargo/reference/features.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeatureGraph {
+    features: BTreeMap<String, Vec<FeatureValue>>,
+    optional_dependencies: BTreeSet<String>,
+    /// All dependencies that features can refer to, i.e. not dev-dependencies
+    dependencies: BTreeSet<String>,
+    /// Features that exist only because an optional dependency isn't referenced with `dep:`
+    implicit_features: BTreeSet<String>,
+}
+
+/// Result of `FeatureGraph::resolve`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ActiveFeatures {
+    /// Enabled features of the package.
+    pub features: BTreeSet<String>,
+    /// Enabled optional dependencies.
+    pub dependencies: BTreeSet<String>,
+    /// Features enabled on dependencies, keyed by dependency name.
+    pub dependency_features: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl<Metadata> Manifest<Metadata> {
+    /// Parse `[features]` into a `FeatureGraph`, checking that all references are valid
+    /// and that no feature depends on itself.
+    pub fn feature_graph(&self) -> Result<FeatureGraph, Error> {
+        let graph = FeatureGraph::build(self);
+        graph.check()?;
+        Ok(graph)
+    }
+}
+
+impl FeatureGraph {
+    /// Features without checking them.
+    pub(crate) fn build<Metadata>(manifest: &Manifest<Metadata>) -> Self {
+        let mut features: BTreeMap<String, Vec<FeatureValue>> = manifest
+            .features
+            .iter()
+            .flatten()
+            .map(|(name, values)| {
+                let values = values.iter().map(|v| FeatureValue::parse(v)).collect();
+                (name.clone(), values)
+            })
+            .collect();
+
+        // Dev-dependencies can't be optional, and features can't enable their features
+        let dependencies: BTreeSet<String> = manifest
+            .all_dependencies()
+            .filter(|entry| entry.kind != DepKind::Dev)
+            .map(|entry| entry.name.to_string())
+            .collect();
+        let optional_dependencies: BTreeSet<String> = manifest
+            .all_dependencies()
+            .filter(|entry| entry.kind != DepKind::Dev && entry.dep.optional())
+            .map(|entry| entry.name.to_string())
+            .collect();
+
+        let referenced_with_dep: BTreeSet<&str> = features
+            .values()
+            .flatten()
+            .filter_map(|value| match value {
+                FeatureValue::Dep(dep) => Some(dep.as_str()),
+                _ => None,
+            })
+            .collect();
+        let implicit_features: BTreeSet<String> = optional_dependencies
+            .iter()
+            .filter(|dep| {
+                !referenced_with_dep.contains(dep.as_str()) && !features.contains_key(*dep)
+            })
+            .cloned()
+            .collect();
+        for dep in &implicit_features {
+            features.insert(dep.clone(), vec![FeatureValue::Dep(dep.clone())]);
+        }
+
+        Self {
+            features,
+            optional_dependencies,
+            dependencies,
+            implicit_features,
+        }
+    }
+
+    fn check(&self) -> Result<(), Error> {
+        for (name, values) in &self.features {
+            for value in values {
+                match value {
+                    FeatureValue::Feature(feature) if !self.features.contains_key(feature) => {
+                        return Err(Error::InvalidFeature(format!(
+                            "feature `{}` includes `{}`, which is neither a feature nor an optional dependency",
+                            name, feature
+                        )))
+                    }
+                    FeatureValue::Dep(dep) if !self.optional_dependencies.contains(dep) => {
+                        return Err(Error::InvalidFeature(format!(
+                            "feature `{}` includes `dep:{}`, but `{}` is not an optional dependency",
+                            name, dep, dep
+                        )))
+                    }
+                    FeatureValue::DepFeature { dep, .. } if !self.dependencies.contains(dep) => {
+                        return Err(Error::InvalidFeature(format!(
+                            "feature `{}` includes `{}`, but `{}` is not a dependency",
+                            name, value, dep
+                        )))
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut done = BTreeSet::new();
+        for name in self.features.keys() {
+            self.check_cycles(name, &mut Vec::new(), &mut done)?;
+        }
+        Ok(())
+    }
+
+    fn check_cycles<'a>(
+        &'a self,
+        name: &'a str,
+        path: &mut Vec<&'a str>,
+        done: &mut BTreeSet<&'a str>,
+    ) -> Result<(), Error> {
+        if let Some(start) = path.iter().position(|&f| f == name) {
+            let mut cycle: Vec<String> = path[start..].iter().map(|f| f.to_string()).collect();
+            cycle.push(name.to_string());
+            return Err(Error::FeatureCycle(cycle));
+        }
+        if done.contains(name) {
+            return Ok(());
+        }
+        path.push(name);
+        for value in self.features.get(name).into_iter().flatten() {
+            if let FeatureValue::Feature(feature) = value {
+                self.check_cycles(feature, path, done)?;
+            }
+        }
+        path.pop();
+        done.insert(name);
+        Ok(())
+    }
+
+    /// All features, including implicit features of optional dependencies, with their values.
+    pub fn features(&self) -> &BTreeMap<String, Vec<FeatureValue>> {
+        &self.features
+    }
+
+    /// Names of optional dependencies, which can be enabled by features.
+    pub fn optional_dependencies(&self) -> &BTreeSet<String> {
+        &self.optional_dependencies
+    }
+
+    /// Compute which features and optional dependencies are active when `enabled` features
+    /// are requested, like `cargo build --features`. `default_features` adds the `default`
+    /// feature, if there is one.
+    pub fn resolve<S: AsRef<str>>(
+        &self,
+        enabled: &[S],
+        default_features: bool,
+    ) -> Result<ActiveFeatures, Error> {
+        let mut active = ActiveFeatures::default();
+        let mut weak = Vec::new();
+        let mut queue: Vec<&str> = enabled.iter().map(|f| f.as_ref()).collect();
+        if default_features && self.features.contains_key("default") {
+            queue.push("default");
+        }
+
+        while let Some(name) = queue.pop() {
+            let values = self.features.get(name).ok_or_else(|| {
+                Error::InvalidFeature(format!("the package has no feature `{}`", name))
+            })?;
+            if !active.features.insert(name.to_string()) {
+                continue;
+            }
+            for value in values {
+                match value {
+                    FeatureValue::Feature(feature) => queue.push(feature),
+                    FeatureValue::Dep(dep) => {
+                        active.dependencies.insert(dep.clone());
+                    }
+                    FeatureValue::DepFeature {
+                        dep,
+                        feature,
+                        weak: true,
+                    } => weak.push((dep, feature)),
+                    FeatureValue::DepFeature { dep, feature, .. } => {
+                        if self.optional_dependencies.contains(dep) {
+                            active.dependencies.insert(dep.clone());
+                            if self.implicit_features.contains(dep) {
+                                queue.push(dep);
+                            }
+                        }
+                        active.enable_dependency_feature(dep, feature);
+                    }
+                }
+            }
+        }
+
+        for (dep, feature) in weak {
+            if !self.optional_dependencies.contains(dep) || active.dependencies.contains(dep) {
+                active.enable_dependency_feature(dep, feature);
+            }
+        }
+        Ok(active)
+    }
+}
+
+impl ActiveFeatures {
+    fn enable_dependency_feature(&mut self, dep: &str, feature: &str) {
+        self.dependency_features
+            .entry(dep.to_string())
+            .or_default()
+            .insert(feature.to_string());
+    }
+}
@@ -6,9 +6,10 @@
 use serde::Deserializer;
 use serde::{Deserialize, Serialize, Serializer};
 use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
 use std::io;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub use toml::Value;
 
@@ -16,11 +17,35 @@ pub type DepsSet = BTreeMap<String, Dependency>;
 pub type TargetDepsSet = BTreeMap<String, Target>;
 pub type FeatureSet = BTreeMap<String, Vec<String>>;
 pub type PatchSet = BTreeMap<String, DepsSet>;
+pub type ReplaceSet = BTreeMap<PackageIdSpec, Dependency>;
 
 mod afs;
+mod dependencies;
+mod document;
 mod error;
+mod features;
+mod platform;
+mod profile;
+mod spec;
+#[cfg(feature = "tarball")]
+mod tarball;
+mod targets;
+mod validate;
+mod workspace;
+use crate::afs::SubFilesystem;
 pub use crate::afs::*;
+pub use crate::dependencies::{DependencyMut, DependencyRef};
+pub use crate::document::ManifestDocument;
 pub use crate::error::Error;
+pub use crate::features::{ActiveFeatures, FeatureGraph, FeatureValue};
+pub use crate::platform::{Cfg, CfgExpr, Platform};
+pub use crate::profile::*;
+pub use crate::spec::{PackageIdSpec, SourceKind};
+#[cfg(feature = "tarball")]
+pub use crate::tarball::TarballFilesystem;
+use crate::targets::{complete_products, has_file};
+pub use crate::targets::{InferenceWarning, ProductKind};
+pub use crate::validate::{Diagnostic, Severity};
 use serde::de::{Error as _, Unexpected};
 use std::str::FromStr;
 
@@ -60,6 +85,9 @@ pub struct Manifest<Metadata = Value> {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub patch: Option<PatchSet>,
+    /// Deprecated in favor of `patch`, see `Manifest::migrate_replace_to_patch`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replace: Option<ReplaceSet>,
 
     /// Note that due to autolibs feature this is not the complete list
     /// unless you run `complete_from_path`
@@ -70,6 +98,9 @@ pub struct Manifest<Metadata = Value> {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub badges: Option<Badges>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lints: Option<MaybeInherited<Lints>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -92,6 +123,9 @@ pub struct Workspace {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub package: Option<WorkspacePackage>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lints: Option<Lints>,
 }
 
 /// The workspace.package table is where you define keys that can be inherited by members of a
@@ -163,6 +197,40 @@ impl Manifest<Value> {
     pub fn from_path(cargo_toml_path: impl AsRef<Path>) -> Result<Self, Error> {
         Self::from_path_with_metadata(cargo_toml_path)
     }
+
+    /// Parse contents from a `Cargo.toml` file on disk, and replace fields inherited
+    /// with `{ workspace = true }` by the values defined in the workspace root.
+    ///
+    /// Calls `complete_from_path`.
+    pub fn from_path_resolved(cargo_toml_path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::from_path_resolved_with_metadata(cargo_toml_path)
+    }
+
+    /// Parse the `Cargo.toml` at the root of `fs`, which doesn't have to be on disk.
+    ///
+    /// Calls `complete_from_abstract_filesystem`.
+    pub fn from_abstract_filesystem(fs: impl AbstractFilesystem) -> Result<Self, Error> {
+        Self::from_abstract_filesystem_with_metadata(fs)
+    }
+
+    /// Parse the `Cargo.toml` in `manifest_dir` of `fs`, and replace fields inherited
+    /// with `{ workspace = true }` by the values defined in the workspace root.
+    ///
+    /// Calls `complete_from_abstract_filesystem`.
+    pub fn from_abstract_filesystem_resolved(
+        fs: impl AbstractFilesystem,
+        manifest_dir: &str,
+    ) -> Result<Self, Error> {
+        Self::from_abstract_filesystem_resolved_with_metadata(fs, manifest_dir)
+    }
+
+    /// Parse the `Cargo.toml` of a published `.crate` file, without unpacking it.
+    ///
+    /// Calls `complete_from_abstract_filesystem` with a `TarballFilesystem`.
+    #[cfg(feature = "tarball")]
+    pub fn from_crate_file(crate_file_path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::from_crate_file_with_metadata(crate_file_path)
+    }
 }
 
 impl FromStr for Manifest<Value> {
@@ -201,21 +269,122 @@ impl<Metadata: for<'a> Deserialize<'a>> Manifest<Metadata> {
     ///
     /// Calls `complete_from_path`
     pub fn from_path_with_metadata(cargo_toml_path: impl AsRef<Path>) -> Result<Self, Error> {
+        let (manifest_dir, file_name) = split_manifest_path(cargo_toml_path.as_ref())?;
+        let fs = Filesystem::new(manifest_dir);
+        let mut manifest = Self::from_slice_with_metadata(&fs.read_file(file_name)?)?;
+        manifest.complete_from_abstract_filesystem(&fs)?;
+        Ok(manifest)
+    }
+
+    /// Parse contents from `Cargo.toml` file on disk, with custom Serde-compatible metadata type,
+    /// and resolve fields inherited from the workspace.
+    ///
+    /// The workspace root is either the manifest itself (if it has a `[workspace]` table),
+    /// the one `package.workspace` points to, or the closest parent directory whose `Cargo.toml`
+    /// has a `[workspace]` table.
+    ///
+    /// Calls `complete_from_path`
+    pub fn from_path_resolved_with_metadata(
+        cargo_toml_path: impl AsRef<Path>,
+    ) -> Result<Self, Error> {
         let cargo_toml_path = cargo_toml_path.as_ref();
-        let cargo_toml_content = fs::read(cargo_toml_path)?;
-        let mut manifest = Self::from_slice_with_metadata(&cargo_toml_content)?;
+        let (manifest_dir, _) = split_manifest_path(cargo_toml_path)?;
+        let mut manifest = Self::from_slice_with_metadata(&fs::read(cargo_toml_path)?)?;
+        manifest.resolve_inherited(
+            |package_workspace| {
+                workspace::find_workspace_root_on_disk(manifest_dir, package_workspace)
+            },
+            &cargo_toml_path.display(),
+        )?;
         manifest.complete_from_path(cargo_toml_path)?;
         Ok(manifest)
     }
 
+    /// Parse the `Cargo.toml` at the root of `fs`, with custom Serde-compatible metadata type.
+    ///
+    /// Calls `complete_from_abstract_filesystem`
+    pub fn from_abstract_filesystem_with_metadata(
+        fs: impl AbstractFilesystem,
+    ) -> Result<Self, Error> {
+        let mut manifest = Self::from_slice_with_metadata(&fs.read_file("Cargo.toml")?)?;
+        manifest.complete_from_abstract_filesystem(&fs)?;
+        Ok(manifest)
+    }
+
+    /// Parse the `Cargo.toml` in `manifest_dir` of `fs`, with custom Serde-compatible metadata
+    /// type, and resolve fields inherited from the workspace.
+    ///
+    /// `manifest_dir` is relative to the root of `fs` (`""` for the root itself). The workspace
+    /// root is found like in `from_path_resolved_with_metadata`, but never above the root of `fs`.
+    ///
+    /// Calls `complete_from_abstract_filesystem`
+    pub fn from_abstract_filesystem_resolved_with_metadata(
+        fs: impl AbstractFilesystem,
+        manifest_dir: &str,
+    ) -> Result<Self, Error> {
+        let manifest_dir = manifest_dir.trim_start_matches("./").trim_end_matches('/');
+        let manifest_dir = if manifest_dir == "." {
+            ""
+        } else {
+            manifest_dir
+        };
+        let sub_fs = SubFilesystem::new(&fs, manifest_dir);
+        let cargo_toml_path = Path::new(manifest_dir).join("Cargo.toml");
+
+        let mut manifest = Self::from_slice_with_metadata(&sub_fs.read_file("Cargo.toml")?)?;
+        manifest.resolve_inherited(
+            |package_workspace| {
+                workspace::find_workspace_root(&fs, manifest_dir, package_workspace)
+            },
+            &cargo_toml_path.display(),
+        )?;
+        manifest.complete_from_abstract_filesystem(&sub_fs)?;
+        Ok(manifest)
+    }
+
+    /// Parse the `Cargo.toml` of a published `.crate` file, with custom Serde-compatible
+    /// metadata type.
+    ///
+    /// Published manifests are normalized, so they don't inherit anything from a workspace.
+    ///
+    /// Calls `complete_from_abstract_filesystem`
+    #[cfg(feature = "tarball")]
+    pub fn from_crate_file_with_metadata(crate_file_path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::from_abstract_filesystem_with_metadata(TarballFilesystem::open(crate_file_path)?)
+    }
+
+    /// Inherit fields from the workspace root, if the manifest isn't one itself.
+    /// `find_workspace_root` is given `package.workspace`.
+    fn resolve_inherited(
+        &mut self,
+        find_workspace_root: impl FnOnce(Option<&str>) -> Result<Option<(PathBuf, Workspace)>, Error>,
+        cargo_toml_path: &dyn fmt::Display,
+    ) -> Result<(), Error> {
+        if let Some(workspace) = self.workspace.clone() {
+            return self.inherit_workspace(&workspace, Path::new(""));
+        }
+        let package_workspace = self.package.as_ref().and_then(|p| p.workspace.as_deref());
+        match find_workspace_root(package_workspace)? {
+            Some((workspace_dir, workspace)) => self.inherit_workspace(&workspace, &workspace_dir),
+            None => self
+                .inherit_workspace(&Workspace::default(), Path::new(""))
+                .map_err(|err| match err {
+                    Error::InheritedUnknownValue(key) => Error::WorkspaceIntegrity(format!(
+                        "`{}` is inherited from the workspace, but no workspace root was found for {}",
+                        key, cargo_toml_path
+                    )),
+                    err => err,
+                }),
+        }
+    }
+
     /// `Cargo.toml` may not contain explicit information about `[lib]`, `[[bin]]` and
     /// `[package].build`, which are inferred based on files on disk.
     ///
     /// This scans the disk to make the data in the manifest as complete as possible.
-    pub fn complete_from_path(&mut self, path: &Path) -> Result<(), Error> {
-        let manifest_dir = path
-            .parent()
-            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "bad path"))?;
+    /// See `complete_from_abstract_filesystem` for the returned warnings.
+    pub fn complete_from_path(&mut self, path: &Path) -> Result<Vec<InferenceWarning>, Error> {
+        let (manifest_dir, _) = split_manifest_path(path)?;
         self.complete_from_abstract_filesystem(Filesystem::new(manifest_dir))
     }
 
@@ -224,131 +393,158 @@ impl<Metadata: for<'a> Deserialize<'a>> Manifest<Metadata> {
     ///
     /// You can provide any implementation of directory scan, which doesn't have to
     /// be reading straight from disk (might scan a tarball or a git repo, for example).
+    /// See `MemoryFilesystem`, and `TarballFilesystem` with the `tarball` feature.
+    ///
+    /// Returns warnings about files that could not be turned into products unambiguously.
     pub fn complete_from_abstract_filesystem(
         &mut self,
         fs: impl AbstractFilesystem,
-    ) -> Result<(), Error> {
-        if let Some(ref mut package) = self.package {
-            let src = match fs.file_names_in("src") {
-                Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Default::default()),
-                result => result,
-            }?;
-
-            let edition = match package.edition {
-                Some(MaybeInherited::Local(edition)) => Some(edition),
-                _ => None,
-            };
-
-            if let Some(ref mut lib) = self.lib {
-                lib.required_features.clear(); // not applicable
-            } else if src.contains("lib.rs") {
-                self.lib = Some(Product {
-                    name: Some(package.name.replace('-', "_")),
-                    path: Some("src/lib.rs".to_string()),
-                    edition,
-                    crate_type: Some(vec!["rlib".to_string()]),
-                    ..Product::default()
-                })
+    ) -> Result<Vec<InferenceWarning>, Error> {
+        let mut warnings = Vec::new();
+        let package = match self.package {
+            Some(ref mut package) => package,
+            None => return Ok(warnings),
+        };
+        if let Err(err) = fs.file_names_in("src") {
+            if err.kind() != io::ErrorKind::NotFound {
+                return Err(err.into());
             }
-
-            if package.autobins && self.bin.is_none() {
-                let mut bin = autoset(package, "src/bin", &fs);
-                if src.contains("main.rs") {
-                    bin.push(Product {
-                        name: Some(package.name.clone()),
-                        path: Some("src/main.rs".to_string()),
-                        edition,
-                        ..Product::default()
-                    })
-                }
-                self.bin = Some(bin);
-            }
-            if package.autoexamples && self.example.is_none() {
-                self.example = Some(autoset(package, "examples", &fs));
-            }
-            if package.autotests && self.test.is_none() {
-                self.test = Some(autoset(package, "tests", &fs));
+        }
+        let edition = match package.edition {
+            Some(MaybeInherited::Local(ref edition)) => Some(edition),
+            _ => None,
+        };
+
+        if let Some(ref mut lib) = self.lib {
+            lib.required_features.clear(); // not applicable
+            if lib.name.is_none() {
+                lib.name = Some(package.name.replace('-', "_"));
             }
-            if package.autobenches && self.bench.is_none() {
-                self.bench = Some(autoset(package, "benches", &fs));
+            if lib.path.is_none() {
+                lib.path = Some("src/lib.rs".to_string());
             }
+        } else if package.autolib && has_file(&fs, "src", "lib.rs") {
+            self.lib = Some(Product {
+                name: Some(package.name.replace('-', "_")),
+                path: Some("src/lib.rs".to_string()),
+                edition: edition.cloned(),
+                crate_type: Some(vec!["rlib".to_string()]),
+                ..Product::default()
+            })
+        }
 
-            if package.build.is_none()
-                && fs
-                    .file_names_in(".")
-                    .map_or(false, |dir| dir.contains("build.rs"))
-            {
-                package.build = Some(Value::String("build.rs".to_string()));
-            }
+        let products = [
+            (&mut self.bin, ProductKind::Bin, package.autobins),
+            (
+                &mut self.example,
+                ProductKind::Example,
+                package.autoexamples,
+            ),
+            (&mut self.test, ProductKind::Test, package.autotests),
+            (&mut self.bench, ProductKind::Bench, package.autobenches),
+        ];
+        for (products, kind, autodiscover) in products {
+            complete_products(
+                products,
+                kind,
+                autodiscover,
+                &package.name,
+                edition,
+                &fs,
+                &mut warnings,
+            );
         }
-        Ok(())
+
+        if package.build.is_none() && has_file(&fs, ".", "build.rs") {
+            package.build = Some(Value::String("build.rs".to_string()));
+        }
+        Ok(warnings)
     }
 }
 
-fn autoset<T>(package: &Package<T>, dir: &str, fs: &dyn AbstractFilesystem) -> Vec<Product> {
-    let mut out = Vec::new();
-    let edition = match package.edition {
-        Some(MaybeInherited::Local(edition)) => Some(edition),
-        _ => None,
+/// Directory and file name of a `Cargo.toml` path. The directory of a bare file name is `.`.
+fn split_manifest_path(path: &Path) -> Result<(&Path, &str), Error> {
+    let bad_path = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a path to a file", path.display()),
+        )
     };
-    if let Ok(bins) = fs.file_names_in(dir) {
-        for name in bins {
-            let rel_path = format!("{}/{}", dir, name);
-            if name.ends_with(".rs") {
-                out.push(Product {
-                    name: Some(name.trim_end_matches(".rs").into()),
-                    path: Some(rel_path),
-                    edition,
-                    ..Product::default()
-                })
-            } else if let Ok(sub) = fs.file_names_in(&rel_path) {
-                if sub.contains("main.rs") {
-                    out.push(Product {
-                        name: Some(name.into()),
-                        path: Some(rel_path + "/main.rs"),
-                        edition,
-                        ..Product::default()
-                    })
-                }
-            }
-        }
+    let file_name = path
+        .file_name()
+        .and_then(|f| f.to_str())
+        .ok_or_else(bad_path)?;
+    match path.parent().ok_or_else(bad_path)? {
+        dir if dir.as_os_str().is_empty() => Ok((Path::new("."), file_name)),
+        dir => Ok((dir, file_name)),
     }
-    out
 }
 
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
-pub struct Profiles {
-    pub release: Option<Profile>,
-    pub dev: Option<Profile>,
-    pub test: Option<Profile>,
-    pub bench: Option<Profile>,
-    pub doc: Option<Profile>,
+/// The `[lints]` table, with a table of lints for each tool.
+///
+/// See https://doc.rust-lang.org/cargo/reference/manifest.html#the-lints-section
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Lints {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rust: Option<LintSet>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clippy: Option<LintSet>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rustdoc: Option<LintSet>,
 
+    /// Lints of other tools
     #[serde(flatten)]
-    pub custom: BTreeMap<String, Profile>,
+    pub tools: BTreeMap<String, LintSet>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub type LintSet = BTreeMap<String, Lint>;
+
+/// A lint is configured either with just a level, e.g. `unsafe_code = "forbid"`, or with a
+/// table, e.g. `unsafe_code = { level = "forbid", priority = -1 }`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Lint {
+    Level(LintLevel),
+    Detailed(LintDetail),
+}
+
+impl Lint {
+    pub fn level(&self) -> LintLevel {
+        match *self {
+            Lint::Level(level) => level,
+            Lint::Detailed(ref d) => d.level,
+        }
+    }
+
+    /// Lints and groups with a lower priority are passed to the compiler first,
+    /// so higher priorities override them. Defaults to 0.
+    pub fn priority(&self) -> i8 {
+        match *self {
+            Lint::Level(_) => 0,
+            Lint::Detailed(ref d) => d.priority.unwrap_or(0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
-pub struct Profile {
-    #[serde(alias = "opt_level")]
-    pub opt_level: Option<Value>,
-    pub debug: Option<Value>,
-    pub rpath: Option<bool>,
-    pub inherits: Option<String>,
-    pub lto: Option<Value>,
-    #[serde(alias = "debug_assertions")]
-    pub debug_assertions: Option<bool>,
-    #[serde(alias = "codegen_units")]
-    pub codegen_units: Option<u16>,
-    pub panic: Option<String>,
-    pub incremental: Option<bool>,
-    #[serde(alias = "overflow_checks")]
-    pub overflow_checks: Option<bool>,
-    #[serde(default)]
-    pub package: BTreeMap<String, Value>,
-    /// profile overrides
-    pub build_override: Option<Value>,
+pub struct LintDetail {
+    pub level: LintLevel,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i8>,
+    /// Expected `cfg`s, only used by the `unexpected_cfgs` lint,
+    /// e.g. `['cfg(fuzzing)']`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub check_cfg: Option<Vec<String>>,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+    Forbid,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -438,7 +634,7 @@ impl Default for Product {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Target {
     #[serde(default)]
@@ -449,6 +645,28 @@ pub struct Target {
     pub build_dependencies: DepsSet,
 }
 
+/// The dependency table a dependency is declared in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DepKind {
+    /// `[dependencies]`
+    Normal,
+    /// `[dev-dependencies]`
+    Dev,
+    /// `[build-dependencies]`
+    Build,
+}
+
+impl DepKind {
+    /// Name of the TOML table for this kind of dependency, e.g. `dev-dependencies`.
+    pub fn table_name(self) -> &'static str {
+        match self {
+            DepKind::Normal => "dependencies",
+            DepKind::Dev => "dev-dependencies",
+            DepKind::Build => "build-dependencies",
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum Dependency {
@@ -479,7 +697,7 @@ impl Dependency {
     }
 
     pub fn optional(&self) -> bool {
-        self.detail().map_or(false, |d| d.optional.unwrap_or(false))
+        self.detail().is_some_and(|d| d.optional.unwrap_or(false))
     }
 
     // `Some` if it overrides the package name.
@@ -496,8 +714,11 @@ impl Dependency {
         self.detail().and_then(|d| d.git.as_deref())
     }
 
-    // `true` if it's an usual crates.io dependency,
-    // `false` if git/path/alternative registry
+    /// `true` if it's an usual crates.io dependency,
+    /// `false` if git/path/alternative registry
+    ///
+    /// This only checks that none of these keys are set, so it's also `true` for dependencies
+    /// inherited from the workspace and for some invalid ones. See `source` for a stricter check.
     pub fn is_crates_io(&self) -> bool {
         match *self {
             Dependency::Simple(_) => true,
@@ -513,6 +734,99 @@ impl Dependency {
             }
         }
     }
+
+    /// The version requirement, parsed.
+    ///
+    /// Dependencies without a version requirement, e.g. git dependencies, accept any version.
+    /// The error doesn't name the dependency, since it's only known to the table it's in;
+    /// `DependencyRef::semver_req` (see `Manifest::all_dependencies`) adds it.
+    #[cfg(feature = "semver")]
+    pub fn semver_req(&self) -> Result<semver::VersionReq, Error> {
+        if self.detail().is_some_and(|d| d.workspace == Some(true)) {
+            return Err(Error::InvalidDependency(
+                "dependency is inherited from the workspace, so its version requirement is not known"
+                    .into(),
+            ));
+        }
+        semver::VersionReq::parse(self.req()).map_err(|err| {
+            Error::Semver(
+                format!("invalid version requirement `{}`", self.req()),
+                std::sync::Arc::new(err),
+            )
+        })
+    }
+
+    /// Where the dependency comes from.
+    ///
+    /// A `path` dependency may also have a `version` and `registry`, which are used instead of
+    /// the path when the package is published; it is still reported as `Path`.
+    /// Fails for conflicting keys, e.g. both `git` and `path`, or `branch` without `git`,
+    /// and for dependencies that are inherited from the workspace.
+    pub fn source(&self) -> Result<DependencySource, Error> {
+        let d = match *self {
+            Dependency::Simple(_) => return Ok(DependencySource::CratesIo),
+            Dependency::Detailed(ref d) => d,
+        };
+        let conflict = |a: &str, b: &str| {
+            Err(Error::InvalidDependency(format!(
+                "dependency specifies both `{}` and `{}`, but only one of them is allowed",
+                a, b
+            )))
+        };
+        if d.workspace == Some(true) {
+            return Err(Error::InvalidDependency(
+                "dependency is inherited from the workspace, so its source is not known".into(),
+            ));
+        }
+        let references = [
+            (
+                "branch",
+                d.branch.as_ref().map(|b| GitReference::Branch(b.clone())),
+            ),
+            ("tag", d.tag.as_ref().map(|t| GitReference::Tag(t.clone()))),
+            ("rev", d.rev.as_ref().map(|r| GitReference::Rev(r.clone()))),
+        ];
+        let mut set = references
+            .into_iter()
+            .filter_map(|(key, r)| Some((key, r?)));
+        let reference = set.next();
+        if let (Some((a, _)), Some((b, _))) = (&reference, set.next()) {
+            return conflict(a, b);
+        }
+        if let (None, Some((key, _))) = (&d.git, &reference) {
+            return Err(Error::InvalidDependency(format!(
+                "dependency specifies `{}`, which requires `git`",
+                key
+            )));
+        }
+        if let Some(ref registry) = d.registry {
+            if d.registry_index.is_some() {
+                return conflict("registry", "registry-index");
+            }
+            if d.git.is_some() {
+                return conflict("git", "registry");
+            }
+            if d.path.is_none() {
+                return Ok(match registry.as_str() {
+                    "crates-io" => DependencySource::CratesIo,
+                    _ => DependencySource::Registry {
+                        name: registry.clone(),
+                    },
+                });
+            }
+        }
+        Ok(match (&d.git, &d.path, &d.registry_index) {
+            (Some(_), Some(_), _) => return conflict("git", "path"),
+            (Some(_), _, Some(_)) => return conflict("git", "registry-index"),
+            (Some(url), None, None) => DependencySource::Git {
+                url: url.clone(),
+                reference: reference.map_or(GitReference::DefaultBranch, |(_, r)| r),
+            },
+            (None, Some(path), _) => DependencySource::Path { path: path.clone() },
+            (None, None, Some(url)) => DependencySource::RegistryIndex { url: url.clone() },
+            (None, None, None) => DependencySource::CratesIo,
+        })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
@@ -547,6 +861,155 @@ pub struct DependencyDetail {
     pub default_features: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub package: Option<String>,
+    /// Depend on the binaries or libraries built by the dependency (`-Z bindeps`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artifact: Option<Artifact>,
+    /// Platform to build the `artifact` for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<ArtifactTarget>,
+    /// Whether the `artifact` dependency can also be used as a normal Rust library.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lib: Option<bool>,
+}
+
+/// Result of `Dependency::source`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DependencySource {
+    /// The default registry, also when set explicitly with `registry = "crates-io"`.
+    CratesIo,
+    /// An alternative registry configured under this name in `.cargo/config.toml`.
+    Registry {
+        name: String,
+    },
+    /// A registry given by the URL of its index.
+    RegistryIndex {
+        url: String,
+    },
+    Path {
+        path: String,
+    },
+    Git {
+        url: String,
+        reference: GitReference,
+    },
+}
+
+/// The commit of a `git` dependency to use.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GitReference {
+    Branch(String),
+    Tag(String),
+    Rev(String),
+    /// Neither `branch`, `tag` nor `rev` is set.
+    DefaultBranch,
+}
+
+/// `artifact` of a dependency: a single kind, or a list of kinds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Artifact {
+    Single(ArtifactKind),
+    Multiple(Vec<ArtifactKind>),
+}
+
+impl Artifact {
+    pub fn kinds(&self) -> &[ArtifactKind] {
+        match self {
+            Artifact::Single(kind) => std::slice::from_ref(kind),
+            Artifact::Multiple(kinds) => kinds,
+        }
+    }
+}
+
+/// A kind of artifact, e.g. `bin` or `bin:name`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ArtifactKind {
+    /// `bin`: all binaries of the dependency
+    Bin,
+    /// `bin:name`: only the binary `name`
+    BinName(String),
+    Cdylib,
+    Staticlib,
+}
+
+impl FromStr for ArtifactKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "bin" => ArtifactKind::Bin,
+            "cdylib" => ArtifactKind::Cdylib,
+            "staticlib" => ArtifactKind::Staticlib,
+            _ => match s.strip_prefix("bin:") {
+                Some(name) if !name.is_empty() => ArtifactKind::BinName(name.to_string()),
+                _ => {
+                    return Err(Error::InvalidDependency(format!(
+                        "`{}` is not a valid artifact kind, expected `bin`, `bin:<name>`, `cdylib` or `staticlib`",
+                        s
+                    )))
+                }
+            },
+        })
+    }
+}
+
+impl fmt::Display for ArtifactKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArtifactKind::Bin => f.write_str("bin"),
+            ArtifactKind::BinName(name) => write!(f, "bin:{}", name),
+            ArtifactKind::Cdylib => f.write_str("cdylib"),
+            ArtifactKind::Staticlib => f.write_str("staticlib"),
+        }
+    }
+}
+
+impl Serialize for ArtifactKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ArtifactKind {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+/// `target` of an artifact dependency.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ArtifactTarget {
+    /// `"target"`: the platform the dependent package is built for
+    Target,
+    /// A target triple, e.g. `wasm32-unknown-unknown`
+    Triple(String),
+}
+
+impl fmt::Display for ArtifactTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArtifactTarget::Target => f.write_str("target"),
+            ArtifactTarget::Triple(triple) => f.write_str(triple),
+        }
+    }
+}
+
+impl Serialize for ArtifactTarget {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ArtifactTarget {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let target = String::deserialize(deserializer)?;
+        Ok(match target.as_str() {
+            "target" => ArtifactTarget::Target,
+            _ => ArtifactTarget::Triple(target),
+        })
+    }
 }
 
 /// Used as a wrapper for properties that may be inherited by workspace-level settings.
@@ -567,6 +1030,14 @@ impl<T> MaybeInherited<T> {
     pub fn inherited() -> Self {
         Self::Inherited { workspace: True }
     }
+
+    /// The locally defined value, or `None` if it is inherited from the workspace.
+    pub fn as_local(&self) -> Option<&T> {
+        match self {
+            Self::Inherited { .. } => None,
+            Self::Local(value) => Some(value),
+        }
+    }
 }
 
 /// A type-level representation of a `true` boolean value.
@@ -654,9 +1125,12 @@ pub struct Package<Metadata = Value> {
     pub include: Option<MaybeInherited<Vec<String>>>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "default-run", alias = "default_run")]
     /// The default binary to run by cargo run.
     pub default_run: Option<String>,
 
+    #[serde(default = "default_true")]
+    pub autolib: bool,
     #[serde(default = "default_true")]
     pub autobins: bool,
     #[serde(default = "default_true")]
@@ -671,6 +1145,24 @@ pub struct Package<Metadata = Value> {
     pub resolver: Option<Resolver>,
 }
 
+impl<Metadata> Package<Metadata> {
+    /// `version`, parsed.
+    ///
+    /// Fails if the version is inherited from the workspace and not resolved yet,
+    /// see `Manifest::from_path_resolved`.
+    #[cfg(feature = "semver")]
+    pub fn semver_version(&self) -> Result<semver::Version, Error> {
+        match self.version {
+            MaybeInherited::Local(ref version) => semver::Version::parse(version).map_err(|err| {
+                Error::Semver("invalid `package.version`".into(), std::sync::Arc::new(err))
+            }),
+            MaybeInherited::Inherited { .. } => Err(Error::WorkspaceIntegrity(
+                "`package.version` is inherited from the workspace, but was not resolved".into(),
+            )),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
 #[serde(untagged)]
 pub enum StringOrBool {
@@ -807,32 +1299,114 @@ pub enum MaintenanceStatus {
     Deprecated,
 }
 
-
-
-#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, Serialize, Deserialize)]
-#[derive(Default)]
+/// Editions are ordered chronologically.
+///
+/// Editions released after this crate was published are parsed as `Unknown`, so that
+/// their manifests can still be loaded; `validate` reports them. Only years from 2015 on
+/// are accepted.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, Default)]
 pub enum Edition {
-    #[serde(rename = "2015")]
     #[default]
     E2015,
-    #[serde(rename = "2018")]
     E2018,
-    #[serde(rename = "2021")]
     E2021,
+    E2024,
+    /// The year of an edition that is not known to this crate.
+    Unknown(u16),
+}
+
+impl Edition {
+    /// The year of the edition, e.g. `2021`.
+    pub fn year(self) -> u16 {
+        match self {
+            Edition::E2015 => 2015,
+            Edition::E2018 => 2018,
+            Edition::E2021 => 2021,
+            Edition::E2024 => 2024,
+            Edition::Unknown(year) => year,
+        }
+    }
+}
+
+impl PartialOrd for Edition {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
+impl Ord for Edition {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let key = |e: &Self| (e.year(), matches!(e, Edition::Unknown(_)));
+        key(self).cmp(&key(other))
+    }
+}
 
+impl FromStr for Edition {
+    type Err = Error;
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, Serialize, Deserialize)]
+    fn from_str(edition: &str) -> Result<Self, Self::Err> {
+        match edition {
+            "2015" => Ok(Edition::E2015),
+            "2018" => Ok(Edition::E2018),
+            "2021" => Ok(Edition::E2021),
+            "2024" => Ok(Edition::E2024),
+            // No edition predates 2015, and this excludes leading zeros, which wouldn't
+            // round-trip
+            year if year.len() == 4
+                && year.bytes().all(|b| b.is_ascii_digit())
+                && year > "2015" =>
+            {
+                Ok(Edition::Unknown(year.parse().unwrap_or_default()))
+            }
+            _ => Err(Error::InvalidEdition(format!(
+                "`{}` is not a valid edition, expected a year such as `2021`",
+                edition
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Edition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.year())
+    }
+}
+
+impl Serialize for Edition {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Edition {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, Serialize, Deserialize, Default)]
 pub enum Resolver {
     #[serde(rename = "1")]
+    #[default]
     V1,
     #[serde(rename = "2")]
     V2,
+    #[serde(rename = "3")]
+    V3,
 }
 
-impl Default for Resolver {
-    fn default() -> Self {
-        Self::V1
+impl Resolver {
+    /// The resolver Cargo uses for a workspace root package of this edition,
+    /// if `resolver` isn't set explicitly.
+    pub fn default_for_edition(edition: &Edition) -> Self {
+        if *edition >= Edition::E2024 {
+            Resolver::V3
+        } else if *edition >= Edition::E2021 {
+            Resolver::V2
+        } else {
+            Resolver::V1
+        }
     }
 }
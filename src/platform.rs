@@ -0,0 +1,308 @@
+use crate::{DepKind, DepsSet, Error, Manifest};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::iter::Peekable;
+use std::str::{CharIndices, FromStr};
+
+/// Key of a `[target.'...']` table: a target triple such as `x86_64-pc-windows-msvc`,
+/// or a `cfg(...)` expression.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Platform {
+    Name(String),
+    Cfg(CfgExpr),
+}
+
+/// A `cfg` expression, e.g. `all(unix, target_arch = "x86_64")`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CfgExpr {
+    Not(Box<CfgExpr>),
+    All(Vec<CfgExpr>),
+    Any(Vec<CfgExpr>),
+    Value(Cfg),
+}
+
+/// A single `cfg` option, which is either set or not for a given target.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Cfg {
+    /// e.g. `unix`
+    Name(String),
+    /// e.g. `target_os = "linux"`
+    KeyPair(String, String),
+}
+
+impl Platform {
+    /// Whether this applies to the target `name` (a target triple), which has the `cfg`
+    /// options `cfg` set (as listed by `rustc --print cfg --target <name>`).
+    pub fn matches(&self, name: &str, cfg: &[Cfg]) -> bool {
+        match self {
+            Platform::Name(triple) => triple == name,
+            Platform::Cfg(expr) => expr.matches(cfg),
+        }
+    }
+}
+
+impl CfgExpr {
+    /// Evaluate the expression, given the `cfg` options that are set.
+    pub fn matches(&self, cfg: &[Cfg]) -> bool {
+        match self {
+            CfgExpr::Not(expr) => !expr.matches(cfg),
+            CfgExpr::All(exprs) => exprs.iter().all(|e| e.matches(cfg)),
+            CfgExpr::Any(exprs) => exprs.iter().any(|e| e.matches(cfg)),
+            CfgExpr::Value(value) => cfg.contains(value),
+        }
+    }
+}
+
+impl<Metadata> Manifest<Metadata> {
+    /// `[dependencies]` merged with the dependencies of the `[target.*]` table for `platform`.
+    ///
+    /// Keys are compared after parsing, so e.g. `cfg(all(unix,windows))` and
+    /// `cfg(all(unix, windows))` are the same platform. A target-specific dependency replaces
+    /// a top-level one of the same name. `[target.*]` keys that can't be parsed are skipped;
+    /// `validate` reports them.
+    pub fn dependencies_for_platform(&self, platform: &Platform) -> DepsSet {
+        self.normal_dependencies_where(|key| key == platform)
+    }
+
+    /// `[dependencies]` merged with the dependencies of all `[target.*]` tables that apply to
+    /// the target `name` with the `cfg` options `cfg` set (see `Platform::matches`).
+    ///
+    /// A target-specific dependency replaces a top-level one of the same name. `[target.*]`
+    /// keys that can't be parsed are skipped; `validate` reports them.
+    pub fn dependencies_for_target(&self, name: &str, cfg: &[Cfg]) -> DepsSet {
+        self.normal_dependencies_where(|platform| platform.matches(name, cfg))
+    }
+
+    fn normal_dependencies_where(&self, applies: impl Fn(&Platform) -> bool) -> DepsSet {
+        let mut deps = DepsSet::new();
+        for entry in self.all_dependencies() {
+            if entry.kind != DepKind::Normal {
+                continue;
+            }
+            if let Some(platform) = entry.platform {
+                if !platform.parse::<Platform>().is_ok_and(|p| applies(&p)) {
+                    continue;
+                }
+            }
+            deps.insert(entry.name.to_string(), entry.dep.clone());
+        }
+        deps
+    }
+}
+
+impl FromStr for Platform {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(expr) = s.strip_prefix("cfg(").and_then(|s| s.strip_suffix(')')) {
+            return Ok(Platform::Cfg(expr.parse()?));
+        }
+        let is_triple_char = |c: char| c.is_ascii_alphanumeric() || "-_.".contains(c);
+        if s.is_empty() || !s.chars().all(is_triple_char) {
+            return Err(Error::InvalidPlatform(format!(
+                "`{}` is neither a target triple nor a `cfg(...)` expression",
+                s
+            )));
+        }
+        Ok(Platform::Name(s.to_string()))
+    }
+}
+
+impl FromStr for CfgExpr {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            input: s,
+            chars: s.char_indices().peekable(),
+        };
+        let expr = parser.expr()?;
+        match parser.token()? {
+            None => Ok(expr),
+            Some(token) => Err(parser.error(&format!("unexpected {}", token))),
+        }
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Platform::Name(name) => f.write_str(name),
+            Platform::Cfg(expr) => write!(f, "cfg({})", expr),
+        }
+    }
+}
+
+impl fmt::Display for CfgExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (op, exprs) = match self {
+            CfgExpr::Not(expr) => return write!(f, "not({})", expr),
+            CfgExpr::All(exprs) => ("all", exprs),
+            CfgExpr::Any(exprs) => ("any", exprs),
+            CfgExpr::Value(value) => return value.fmt(f),
+        };
+        write!(f, "{}(", op)?;
+        for (i, expr) in exprs.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            expr.fmt(f)?;
+        }
+        f.write_str(")")
+    }
+}
+
+impl fmt::Display for Cfg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cfg::Name(name) => f.write_str(name),
+            Cfg::KeyPair(key, value) => write!(f, "{} = \"{}\"", key, value),
+        }
+    }
+}
+
+impl Serialize for Platform {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Platform {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token<'a> {
+    Ident(&'a str),
+    String(&'a str),
+    LeftParen,
+    RightParen,
+    Comma,
+    Equals,
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(ident) => write!(f, "identifier `{}`", ident),
+            Token::String(s) => write!(f, "string \"{}\"", s),
+            Token::LeftParen => f.write_str("`(`"),
+            Token::RightParen => f.write_str("`)`"),
+            Token::Comma => f.write_str("`,`"),
+            Token::Equals => f.write_str("`=`"),
+        }
+    }
+}
+
+/// Recursive descent parser for `cfg` expressions:
+///
+/// ```text
+/// expr := "all" "(" list ")" | "any" "(" list ")" | "not" "(" expr ")"
+///       | ident | ident "=" string
+/// list := [ expr { "," expr } [ "," ] ]
+/// ```
+struct Parser<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn expr(&mut self) -> Result<CfgExpr, Error> {
+        let ident = match self.token()? {
+            Some(Token::Ident(ident)) => ident,
+            Some(token) => return Err(self.error(&format!("expected identifier, found {}", token))),
+            None => return Err(self.error("expected identifier, found end of input")),
+        };
+        match (ident, self.peek_token()?) {
+            ("all" | "any" | "not", Some(Token::LeftParen)) => {
+                self.token()?;
+                let mut exprs = Vec::new();
+                while self.peek_token()? != Some(Token::RightParen) {
+                    exprs.push(self.expr()?);
+                    if self.peek_token()? == Some(Token::Comma) {
+                        self.token()?;
+                    } else {
+                        break;
+                    }
+                }
+                self.expect(Token::RightParen)?;
+                Ok(match ident {
+                    "all" => CfgExpr::All(exprs),
+                    "any" => CfgExpr::Any(exprs),
+                    _ if exprs.len() == 1 => CfgExpr::Not(Box::new(exprs.remove(0))),
+                    _ => return Err(self.error("`not()` takes exactly one expression")),
+                })
+            }
+            (_, Some(Token::Equals)) => {
+                self.token()?;
+                match self.token()? {
+                    Some(Token::String(value)) => Ok(CfgExpr::Value(Cfg::KeyPair(
+                        ident.to_string(),
+                        value.to_string(),
+                    ))),
+                    _ => Err(self.error(&format!("expected a string after `{} =`", ident))),
+                }
+            }
+            _ => Ok(CfgExpr::Value(Cfg::Name(ident.to_string()))),
+        }
+    }
+
+    fn expect(&mut self, expected: Token<'_>) -> Result<(), Error> {
+        match self.token()? {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(self.error(&format!("expected {}, found {}", expected, token))),
+            None => Err(self.error(&format!("expected {}, found end of input", expected))),
+        }
+    }
+
+    fn peek_token(&mut self) -> Result<Option<Token<'a>>, Error> {
+        let chars = self.chars.clone();
+        let token = self.token();
+        self.chars = chars;
+        token
+    }
+
+    fn token(&mut self) -> Result<Option<Token<'a>>, Error> {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+        let (start, c) = match self.chars.next() {
+            Some(next) => next,
+            None => return Ok(None),
+        };
+        let token = match c {
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            ',' => Token::Comma,
+            '=' => Token::Equals,
+            '"' => {
+                let end = loop {
+                    match self.chars.next() {
+                        Some((end, '"')) => break end,
+                        Some(_) => {}
+                        None => return Err(self.error("unterminated string")),
+                    }
+                };
+                Token::String(&self.input[start + 1..end])
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut end = start + c.len_utf8();
+                while let Some((i, c)) = self
+                    .chars
+                    .next_if(|(_, c)| c.is_alphanumeric() || *c == '_')
+                {
+                    end = i + c.len_utf8();
+                }
+                Token::Ident(&self.input[start..end])
+            }
+            c => return Err(self.error(&format!("unexpected character `{}`", c))),
+        };
+        Ok(Some(token))
+    }
+
+    fn error(&self, msg: &str) -> Error {
+        Error::InvalidPlatform(format!("invalid cfg expression `{}`: {}", self.input, msg))
+    }
+}
@@ -0,0 +1,662 @@
+use crate::{Error, PackageIdSpec, Value};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Profiles {
+    pub release: Option<Profile>,
+    pub dev: Option<Profile>,
+    pub test: Option<Profile>,
+    pub bench: Option<Profile>,
+    pub doc: Option<Profile>,
+
+    #[serde(flatten)]
+    pub custom: BTreeMap<String, Profile>,
+}
+
+impl Profiles {
+    /// The profile table with this name, e.g. `[profile.release]`, if it is defined.
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        match name {
+            "release" => self.release.as_ref(),
+            "dev" => self.dev.as_ref(),
+            "test" => self.test.as_ref(),
+            "bench" => self.bench.as_ref(),
+            "doc" => self.doc.as_ref(),
+            _ => self.custom.get(name),
+        }
+    }
+
+    /// Compute the settings Cargo uses for the profile `name`.
+    ///
+    /// Follows the `inherits` chain down to `dev` or `release` (`test` and `doc` implicitly
+    /// inherit from `dev`, `bench` from `release`), starts from Cargo's built-in defaults for
+    /// that profile, and applies the settings of each profile in the chain. `package.<name>`
+    /// overrides and `build-override` are merged along the chain, too.
+    ///
+    /// Fails if a profile is not defined, a custom profile lacks `inherits`, `dev` or `release`
+    /// set it, profiles inherit from each other in a cycle, or a `package` key is not a valid
+    /// package spec.
+    pub fn resolve(&self, name: &str) -> Result<ResolvedProfile, Error> {
+        let mut chain = Vec::new();
+        let mut visited = vec![name];
+        let mut current = name;
+        let root = loop {
+            let profile = self.get(current);
+            for spec in profile.iter().flat_map(|p| p.package.keys()) {
+                if spec != "*" {
+                    parse_package_spec(current, spec)?;
+                }
+            }
+            chain.extend(profile);
+            if let "dev" | "release" = current {
+                if profile.is_some_and(|p| p.inherits.is_some()) {
+                    return Err(Error::InvalidProfile(format!(
+                        "`inherits` must not be specified in root profile `{}`",
+                        current
+                    )));
+                }
+                break current;
+            }
+            let parent = match (current, profile.and_then(|p| p.inherits.as_deref())) {
+                (_, Some(parent)) => parent,
+                ("test" | "doc", None) => "dev",
+                ("bench", None) => "release",
+                (_, None) if profile.is_none() => {
+                    return Err(Error::InvalidProfile(format!(
+                        "profile `{}` is not defined",
+                        current
+                    )))
+                }
+                (_, None) => {
+                    return Err(Error::InvalidProfile(format!(
+                        "profile `{}` must set `inherits`, e.g. `inherits = \"release\"`",
+                        current
+                    )))
+                }
+            };
+            if visited.contains(&parent) {
+                visited.push(parent);
+                return Err(Error::InvalidProfile(format!(
+                    "profile inheritance cycle: {}",
+                    visited.join(" -> ")
+                )));
+            }
+            visited.push(parent);
+            current = parent;
+        };
+
+        let mut merged = Profile::default();
+        for profile in chain.iter().rev() {
+            merged.merge(profile);
+        }
+
+        let mut resolved = ResolvedProfile::defaults(root);
+        resolved.name = name.to_string();
+        resolved.apply(&merged);
+        resolved.package = merged.package;
+
+        let mut build_override = resolved.clone();
+        build_override.opt_level = OptLevel::O0;
+        build_override.codegen_units = 256;
+        build_override.debug = DebugInfo::None;
+        if let Some(ref settings) = merged.build_override {
+            build_override.apply(settings);
+        }
+        resolved.build_override = Some(Box::new(build_override));
+        Ok(resolved)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Profile {
+    #[serde(alias = "opt_level")]
+    pub opt_level: Option<OptLevel>,
+    pub debug: Option<DebugInfo>,
+    pub rpath: Option<bool>,
+    pub inherits: Option<String>,
+    pub lto: Option<Lto>,
+    #[serde(alias = "debug_assertions")]
+    pub debug_assertions: Option<bool>,
+    #[serde(alias = "codegen_units")]
+    pub codegen_units: Option<u16>,
+    pub panic: Option<PanicStrategy>,
+    pub incremental: Option<bool>,
+    #[serde(alias = "overflow_checks")]
+    pub overflow_checks: Option<bool>,
+    pub strip: Option<Strip>,
+    #[serde(alias = "split_debuginfo")]
+    pub split_debuginfo: Option<SplitDebuginfo>,
+    #[serde(alias = "trim_paths")]
+    pub trim_paths: Option<TrimPaths>,
+    /// Overrides for dependencies, keyed by package spec (`*`, `name` or `name@version`).
+    #[serde(default)]
+    pub package: BTreeMap<String, ProfileOverride>,
+    /// profile overrides
+    pub build_override: Option<Box<Profile>>,
+}
+
+impl Profile {
+    /// Override the settings of this profile with those set in `other`.
+    ///
+    /// `package` overrides and `build-override` are merged key by key. `inherits` is kept.
+    pub fn merge(&mut self, other: &Profile) {
+        fn set<T: Clone>(field: &mut Option<T>, other: &Option<T>) {
+            if other.is_some() {
+                field.clone_from(other);
+            }
+        }
+        set(&mut self.opt_level, &other.opt_level);
+        set(&mut self.debug, &other.debug);
+        set(&mut self.rpath, &other.rpath);
+        set(&mut self.lto, &other.lto);
+        set(&mut self.debug_assertions, &other.debug_assertions);
+        set(&mut self.codegen_units, &other.codegen_units);
+        set(&mut self.panic, &other.panic);
+        set(&mut self.incremental, &other.incremental);
+        set(&mut self.overflow_checks, &other.overflow_checks);
+        set(&mut self.strip, &other.strip);
+        set(&mut self.split_debuginfo, &other.split_debuginfo);
+        set(&mut self.trim_paths, &other.trim_paths);
+        for (spec, settings) in &other.package {
+            match self.package.get_mut(spec) {
+                Some(existing) => existing.merge(settings),
+                None => {
+                    self.package.insert(spec.clone(), settings.clone());
+                }
+            }
+        }
+        match (&mut self.build_override, &other.build_override) {
+            (Some(existing), Some(settings)) => existing.merge(settings),
+            (existing, settings) => set(existing, settings),
+        }
+    }
+}
+
+/// Settings that can be overridden for a single package, in `[profile.*.package.<spec>]`.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ProfileOverride {
+    #[serde(alias = "opt_level")]
+    pub opt_level: Option<OptLevel>,
+    pub debug: Option<DebugInfo>,
+    #[serde(alias = "split_debuginfo")]
+    pub split_debuginfo: Option<SplitDebuginfo>,
+    #[serde(alias = "debug_assertions")]
+    pub debug_assertions: Option<bool>,
+    #[serde(alias = "overflow_checks")]
+    pub overflow_checks: Option<bool>,
+    #[serde(alias = "codegen_units")]
+    pub codegen_units: Option<u16>,
+    pub incremental: Option<bool>,
+    pub strip: Option<Strip>,
+}
+
+impl ProfileOverride {
+    /// Override the settings of this override with those set in `other`.
+    pub fn merge(&mut self, other: &ProfileOverride) {
+        self.opt_level = other.opt_level.or(self.opt_level);
+        self.debug = other.debug.or(self.debug);
+        self.split_debuginfo = other.split_debuginfo.or(self.split_debuginfo);
+        self.debug_assertions = other.debug_assertions.or(self.debug_assertions);
+        self.overflow_checks = other.overflow_checks.or(self.overflow_checks);
+        self.codegen_units = other.codegen_units.or(self.codegen_units);
+        self.incremental = other.incremental.or(self.incremental);
+        self.strip = other.strip.clone().or(self.strip.take());
+    }
+}
+
+/// Combined overrides from `package` that apply to the package `name` at `version`.
+///
+/// Like Cargo, `*` is applied first, then the one other spec that matches, which may have a
+/// (partial) version, e.g. `name@1.2`. Cargo rejects profiles in which more than one spec other
+/// than `*` matches a package, and so does this. Note that Cargo doesn't apply `*` to workspace
+/// members.
+fn package_override(
+    profile: &str,
+    package: &BTreeMap<String, ProfileOverride>,
+    name: &str,
+    version: &str,
+) -> Result<ProfileOverride, Error> {
+    let mut matching: Vec<(&String, &ProfileOverride)> = Vec::new();
+    for (spec, settings) in package.iter().filter(|(spec, _)| spec.as_str() != "*") {
+        if parse_package_spec(profile, spec)?.matches(name, version, None) {
+            matching.push((spec, settings));
+        }
+    }
+    if matching.len() > 1 {
+        let specs: Vec<&str> = matching.iter().map(|(spec, _)| spec.as_str()).collect();
+        return Err(Error::InvalidProfile(format!(
+            "multiple package overrides in profile `{}` match package `{} v{}`: {}",
+            profile,
+            name,
+            version,
+            specs.join(", ")
+        )));
+    }
+
+    let mut result = package.get("*").cloned().unwrap_or_default();
+    if let Some((_, settings)) = matching.first() {
+        result.merge(settings);
+    }
+    Ok(result)
+}
+
+/// A key of `[profile.<profile>.package]` other than `*`.
+fn parse_package_spec(profile: &str, spec: &str) -> Result<PackageIdSpec, Error> {
+    spec.parse().map_err(|err| {
+        Error::InvalidProfile(format!(
+            "invalid package spec `{}` in `profile.{}.package`: {}",
+            spec, profile, err
+        ))
+    })
+}
+
+/// Settings of a profile after applying inheritance and Cargo's defaults.
+/// See `Profiles::resolve`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedProfile {
+    pub name: String,
+    /// Never `Quoted`, see `OptLevel::normalized`.
+    pub opt_level: OptLevel,
+    /// Always one of the named settings, see `DebugInfo::normalized`.
+    pub debug: DebugInfo,
+    pub debug_assertions: bool,
+    pub overflow_checks: bool,
+    /// Never one of the other spellings of `"off"`, see `Lto::normalized`.
+    pub lto: Lto,
+    pub panic: PanicStrategy,
+    pub incremental: bool,
+    pub codegen_units: u16,
+    pub rpath: bool,
+    /// Never `Bool` or `"off"`, see `Strip::normalized`.
+    pub strip: Strip,
+    /// `None` if not set, in which case the default depends on the target platform.
+    pub split_debuginfo: Option<SplitDebuginfo>,
+    pub trim_paths: Option<TrimPaths>,
+    /// Overrides for dependencies, keyed by package spec (`*`, `name` or `name@version`).
+    /// See `settings_for`.
+    pub package: BTreeMap<String, ProfileOverride>,
+    /// Settings for build scripts, proc-macros and their dependencies.
+    /// `None` only for this nested profile itself.
+    pub build_override: Option<Box<ResolvedProfile>>,
+}
+
+impl ResolvedProfile {
+    /// Cargo's built-in settings of `dev` or `release`.
+    fn defaults(root: &str) -> Self {
+        let release = root == "release";
+        Self {
+            name: root.to_string(),
+            opt_level: if release { OptLevel::O3 } else { OptLevel::O0 },
+            debug: if release {
+                DebugInfo::None
+            } else {
+                DebugInfo::Full
+            },
+            debug_assertions: !release,
+            overflow_checks: !release,
+            lto: Lto::Bool(false),
+            panic: PanicStrategy::Unwind,
+            incremental: !release,
+            codegen_units: if release { 16 } else { 256 },
+            rpath: false,
+            strip: Strip::None,
+            split_debuginfo: None,
+            trim_paths: None,
+            package: BTreeMap::new(),
+            build_override: None,
+        }
+    }
+
+    /// The settings the package `name` at `version` is compiled with in this profile, after
+    /// applying the matching `package` overrides.
+    ///
+    /// Fails if more than one override other than `*` matches the package, or if a key of
+    /// `package` is not a valid package spec.
+    pub fn settings_for(&self, name: &str, version: &str) -> Result<ResolvedProfile, Error> {
+        let mut settings = self.clone();
+        settings.apply_override(&package_override(&self.name, &self.package, name, version)?);
+        Ok(settings)
+    }
+
+    fn apply(&mut self, profile: &Profile) {
+        self.apply_override(&ProfileOverride {
+            opt_level: profile.opt_level,
+            debug: profile.debug,
+            split_debuginfo: profile.split_debuginfo,
+            debug_assertions: profile.debug_assertions,
+            overflow_checks: profile.overflow_checks,
+            codegen_units: profile.codegen_units,
+            incremental: profile.incremental,
+            strip: profile.strip.clone(),
+        });
+        if let Some(lto) = &profile.lto {
+            self.lto = lto.clone().normalized();
+        }
+        if let Some(panic) = profile.panic {
+            self.panic = panic;
+        }
+        if let Some(rpath) = profile.rpath {
+            self.rpath = rpath;
+        }
+        if profile.trim_paths.is_some() {
+            self.trim_paths.clone_from(&profile.trim_paths);
+        }
+    }
+
+    fn apply_override(&mut self, settings: &ProfileOverride) {
+        if let Some(opt_level) = settings.opt_level {
+            self.opt_level = opt_level.normalized();
+        }
+        if let Some(debug) = settings.debug {
+            self.debug = debug.normalized();
+        }
+        if settings.split_debuginfo.is_some() {
+            self.split_debuginfo = settings.split_debuginfo;
+        }
+        if let Some(debug_assertions) = settings.debug_assertions {
+            self.debug_assertions = debug_assertions;
+        }
+        if let Some(overflow_checks) = settings.overflow_checks {
+            self.overflow_checks = overflow_checks;
+        }
+        if let Some(codegen_units) = settings.codegen_units {
+            self.codegen_units = codegen_units;
+        }
+        if let Some(incremental) = settings.incremental {
+            self.incremental = incremental;
+        }
+        if let Some(strip) = &settings.strip {
+            self.strip = strip.clone().normalized();
+        }
+    }
+}
+
+/// `opt-level`
+///
+/// The variants keep the spelling used in `Cargo.toml`; use `normalized` to compare settings.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum OptLevel {
+    /// `0`: no optimizations
+    O0,
+    /// `1`: basic optimizations
+    O1,
+    /// `2`: some optimizations
+    O2,
+    /// `3`: all optimizations
+    O3,
+    /// `"0"` to `"3"`: a level spelled as a string, which Cargo accepts too
+    Quoted(u8),
+    /// `"s"`: optimize for binary size
+    S,
+    /// `"z"`: optimize for binary size, but also turn off loop vectorization
+    Z,
+}
+
+impl OptLevel {
+    /// The integer equivalent of a `Quoted` setting.
+    pub fn normalized(self) -> Self {
+        match self {
+            OptLevel::Quoted(0) => OptLevel::O0,
+            OptLevel::Quoted(1) => OptLevel::O1,
+            OptLevel::Quoted(2) => OptLevel::O2,
+            OptLevel::Quoted(_) => OptLevel::O3,
+            other => other,
+        }
+    }
+}
+
+/// `debug`
+///
+/// The variants keep the spelling used in `Cargo.toml`; use `normalized` to compare settings.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum DebugInfo {
+    /// `true` is `"full"`, `false` is `"none"`
+    Bool(bool),
+    /// `0` is `"none"`, `1` is `"limited"` and `2` is `"full"`
+    Level(u8),
+    /// `"none"`
+    None,
+    /// `"line-directives-only"`
+    LineDirectivesOnly,
+    /// `"line-tables-only"`
+    LineTablesOnly,
+    /// `"limited"`
+    Limited,
+    /// `"full"`
+    Full,
+}
+
+impl DebugInfo {
+    /// The named equivalent of a `Bool` or `Level` setting.
+    pub fn normalized(self) -> Self {
+        match self {
+            DebugInfo::Bool(false) | DebugInfo::Level(0) => DebugInfo::None,
+            DebugInfo::Level(1) => DebugInfo::Limited,
+            DebugInfo::Bool(true) | DebugInfo::Level(_) => DebugInfo::Full,
+            named => named,
+        }
+    }
+}
+
+/// `lto`
+///
+/// The variants keep the spelling used in `Cargo.toml`; use `normalized` to compare settings.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum Lto {
+    /// `true` is the same as `"fat"`. `false` only performs "thin local LTO",
+    /// which is different from `"off"`.
+    Bool(bool),
+    /// `"off"`: no LTO at all
+    Off,
+    /// `"thin"`
+    Thin,
+    /// `"fat"`: across all crates in the dependency graph
+    Fat,
+    /// Any other string. Cargo treats `"n"`, `"no"` and `"none"` like `"off"`,
+    /// and passes the rest to rustc as is, e.g. `"yes"`.
+    Named(String),
+}
+
+impl Lto {
+    /// `Off` for the other spellings of `"off"`.
+    pub fn normalized(self) -> Self {
+        match self {
+            Lto::Named(name) if matches!(name.as_str(), "n" | "no" | "none") => Lto::Off,
+            other => other,
+        }
+    }
+}
+
+/// `panic`
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PanicStrategy {
+    Unwind,
+    Abort,
+}
+
+/// `strip`
+///
+/// The variants keep the spelling used in `Cargo.toml`; use `normalized` to compare settings.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum Strip {
+    /// `true` is `"symbols"`, `false` is `"none"`
+    Bool(bool),
+    /// `"none"`
+    None,
+    /// `"debuginfo"`
+    Debuginfo,
+    /// `"symbols"`
+    Symbols,
+    /// Any other string. Cargo treats `"off"` like `"none"`, and passes the rest to rustc.
+    Named(String),
+}
+
+impl Strip {
+    /// The named equivalent of a `Bool` setting, and `None` for `"off"`.
+    pub fn normalized(self) -> Self {
+        match self {
+            Strip::Bool(false) => Strip::None,
+            Strip::Bool(true) => Strip::Symbols,
+            Strip::Named(name) if name == "off" => Strip::None,
+            other => other,
+        }
+    }
+}
+
+/// `split-debuginfo`
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SplitDebuginfo {
+    Off,
+    Packed,
+    Unpacked,
+}
+
+/// `trim-paths`
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TrimPaths {
+    /// `true` is `"all"`, `false` is `"none"`
+    Bool(bool),
+    Scope(TrimPathsScope),
+    Scopes(Vec<TrimPathsScope>),
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TrimPathsScope {
+    None,
+    Macro,
+    Diagnostics,
+    Object,
+    All,
+}
+
+impl Serialize for OptLevel {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            OptLevel::O0 => serializer.serialize_i64(0),
+            OptLevel::O1 => serializer.serialize_i64(1),
+            OptLevel::O2 => serializer.serialize_i64(2),
+            OptLevel::O3 => serializer.serialize_i64(3),
+            OptLevel::Quoted(level) => serializer.collect_str(level),
+            OptLevel::S => serializer.serialize_str("s"),
+            OptLevel::Z => serializer.serialize_str("z"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for OptLevel {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::Integer(0) => Ok(OptLevel::O0),
+            Value::Integer(1) => Ok(OptLevel::O1),
+            Value::Integer(2) => Ok(OptLevel::O2),
+            Value::Integer(3) => Ok(OptLevel::O3),
+            Value::String(s) => match s.as_str() {
+                "0" => Ok(OptLevel::Quoted(0)),
+                "1" => Ok(OptLevel::Quoted(1)),
+                "2" => Ok(OptLevel::Quoted(2)),
+                "3" => Ok(OptLevel::Quoted(3)),
+                "s" => Ok(OptLevel::S),
+                "z" => Ok(OptLevel::Z),
+                _ => Err(invalid("opt-level", &s)),
+            },
+            other => Err(invalid("opt-level", &other)),
+        }
+    }
+}
+
+impl Serialize for DebugInfo {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            DebugInfo::Bool(b) => serializer.serialize_bool(b),
+            DebugInfo::Level(level) => serializer.serialize_i64(level.into()),
+            DebugInfo::None => serializer.serialize_str("none"),
+            DebugInfo::LineDirectivesOnly => serializer.serialize_str("line-directives-only"),
+            DebugInfo::LineTablesOnly => serializer.serialize_str("line-tables-only"),
+            DebugInfo::Limited => serializer.serialize_str("limited"),
+            DebugInfo::Full => serializer.serialize_str("full"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for DebugInfo {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::Boolean(b) => Ok(DebugInfo::Bool(b)),
+            Value::Integer(level @ 0..=2) => Ok(DebugInfo::Level(level as u8)),
+            Value::String(s) => match s.as_str() {
+                "none" => Ok(DebugInfo::None),
+                "line-directives-only" => Ok(DebugInfo::LineDirectivesOnly),
+                "line-tables-only" => Ok(DebugInfo::LineTablesOnly),
+                "limited" => Ok(DebugInfo::Limited),
+                "full" => Ok(DebugInfo::Full),
+                _ => Err(invalid("debug", &s)),
+            },
+            other => Err(invalid("debug", &other)),
+        }
+    }
+}
+
+impl Serialize for Lto {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Lto::Bool(b) => serializer.serialize_bool(b),
+            Lto::Off => serializer.serialize_str("off"),
+            Lto::Thin => serializer.serialize_str("thin"),
+            Lto::Fat => serializer.serialize_str("fat"),
+            Lto::Named(ref name) => serializer.serialize_str(name),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Lto {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::Boolean(b) => Ok(Lto::Bool(b)),
+            Value::String(s) => match s.as_str() {
+                "off" => Ok(Lto::Off),
+                "thin" => Ok(Lto::Thin),
+                "fat" => Ok(Lto::Fat),
+                _ => Ok(Lto::Named(s)),
+            },
+            other => Err(invalid("lto", &other)),
+        }
+    }
+}
+
+impl Serialize for Strip {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Strip::Bool(b) => serializer.serialize_bool(b),
+            Strip::None => serializer.serialize_str("none"),
+            Strip::Debuginfo => serializer.serialize_str("debuginfo"),
+            Strip::Symbols => serializer.serialize_str("symbols"),
+            Strip::Named(ref name) => serializer.serialize_str(name),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Strip {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::Boolean(b) => Ok(Strip::Bool(b)),
+            Value::String(s) => match s.as_str() {
+                "none" => Ok(Strip::None),
+                "debuginfo" => Ok(Strip::Debuginfo),
+                "symbols" => Ok(Strip::Symbols),
+                _ => Ok(Strip::Named(s)),
+            },
+            other => Err(invalid("strip", &other)),
+        }
+    }
+}
+
+fn invalid<E: serde::de::Error>(key: &str, value: &dyn std::fmt::Display) -> E {
+    E::custom(format!("invalid value for `{}`: {}", key, value))
+}
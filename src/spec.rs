@@ -0,0 +1,313 @@
+use crate::{Error, GitReference};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// A package ID specification, as used for `[replace]` keys, `[profile.*.package]` keys and
+/// `cargo -p`, e.g. `serde`, `serde@1.0.100`, the legacy `serde:1.0.100`,
+/// `https://github.com/rust-lang/crates.io-index#serde@1.0` or `path+file:///x#foo@0.1.0`.
+///
+/// See https://doc.rust-lang.org/cargo/reference/pkgid-spec.html
+///
+/// Specs are compared by their fields, regardless of how they were written.
+#[derive(Debug, Clone)]
+pub struct PackageIdSpec {
+    /// Taken from the last path segment of the URL if the spec doesn't name the package.
+    pub name: String,
+    /// A version, possibly partial (e.g. `1.0`).
+    pub version: Option<String>,
+    /// URL of the source, without the `kind+` prefix, `?query` and `#fragment`.
+    pub url: Option<String>,
+    /// Kind of the source, if the URL has a `kind+` prefix.
+    pub kind: Option<SourceKind>,
+    /// The spec as written, if it was parsed. See `as_written`.
+    original: Option<String>,
+}
+
+/// The `kind+` prefix of a package ID spec URL.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SourceKind {
+    /// `git+`, with the reference from `?branch=`, `?tag=` or `?rev=`.
+    Git(GitReference),
+    /// `path+`
+    Path,
+    /// `registry+`
+    Registry,
+    /// `sparse+`
+    SparseRegistry,
+}
+
+impl PackageIdSpec {
+    /// A spec that matches any version of `name` from any source.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            version: None,
+            url: None,
+            kind: None,
+            original: None,
+        }
+    }
+
+    /// The spec as it was parsed, unless its fields have been changed since, and the
+    /// canonical form given by `Display` otherwise. For example, the legacy `serde:1.0.100`
+    /// is kept as is, while `Display` gives `serde@1.0.100`. This is used for serialization.
+    pub fn as_written(&self) -> String {
+        match self.original {
+            Some(ref original) if original.parse::<Self>().is_ok_and(|spec| spec == *self) => {
+                original.clone()
+            }
+            _ => self.to_string(),
+        }
+    }
+
+    fn fields(&self) -> (&str, Option<&str>, Option<&str>, Option<&SourceKind>) {
+        (
+            &self.name,
+            self.version.as_deref(),
+            self.url.as_deref(),
+            self.kind.as_ref(),
+        )
+    }
+
+    /// Whether this spec refers to the package `name` at `version`, from the source with the
+    /// URL `source` (with or without `kind+` prefix).
+    ///
+    /// If `source` is `None`, the source is not checked. A partial version matches all
+    /// versions that start with it, e.g. `1.2` matches `1.2.3` but not `1.20.0`.
+    pub fn matches(&self, name: &str, version: &str, source: Option<&str>) -> bool {
+        if self.name != name {
+            return false;
+        }
+        if let Some(ref spec_version) = self.version {
+            if !version_matches(spec_version, version) {
+                return false;
+            }
+        }
+        match (&self.url, source) {
+            (Some(url), Some(source)) => {
+                let source = source.split(['?', '#']).next().unwrap_or_default();
+                let source = match source.split_once('+') {
+                    Some((kind, url)) if !kind.contains("://") => url,
+                    _ => source,
+                };
+                url.trim_end_matches('/') == source.trim_end_matches('/')
+            }
+            _ => true,
+        }
+    }
+}
+
+impl PartialEq for PackageIdSpec {
+    fn eq(&self, other: &Self) -> bool {
+        self.fields() == other.fields()
+    }
+}
+
+impl Eq for PackageIdSpec {}
+
+impl std::hash::Hash for PackageIdSpec {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.fields().hash(state)
+    }
+}
+
+impl PartialOrd for PackageIdSpec {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PackageIdSpec {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.fields().cmp(&other.fields())
+    }
+}
+
+impl FromStr for PackageIdSpec {
+    type Err = Error;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let error = |msg: String| {
+            Error::InvalidPackageIdSpec(format!(
+                "invalid package ID specification `{}`: {}",
+                spec, msg
+            ))
+        };
+        if !spec.contains("://") {
+            let (name, version) = parse_name_and_version(spec).map_err(error)?;
+            return Ok(Self {
+                name,
+                version,
+                url: None,
+                kind: None,
+                original: Some(spec.to_string()),
+            });
+        }
+
+        let (url, fragment) = match spec.split_once('#') {
+            Some((url, fragment)) => (url, Some(fragment)),
+            None => (spec, None),
+        };
+        let (url, query) = match url.split_once('?') {
+            Some((url, query)) => (url, Some(query)),
+            None => (url, None),
+        };
+        let (kind, url) = match url.split_once('+') {
+            Some((kind, url)) if !kind.contains("://") => {
+                let kind = match kind {
+                    "git" => SourceKind::Git(GitReference::DefaultBranch),
+                    "path" => SourceKind::Path,
+                    "registry" => SourceKind::Registry,
+                    "sparse" => SourceKind::SparseRegistry,
+                    _ => return Err(error(format!("unsupported source kind `{}`", kind))),
+                };
+                (Some(kind), url)
+            }
+            _ => (None, url),
+        };
+        let kind = match (kind, query) {
+            (kind, None) => kind,
+            (Some(SourceKind::Git(_)), Some(query)) => {
+                let reference = match query.split_once('=') {
+                    Some(("branch", branch)) => GitReference::Branch(branch.to_string()),
+                    Some(("tag", tag)) => GitReference::Tag(tag.to_string()),
+                    Some(("rev", rev)) => GitReference::Rev(rev.to_string()),
+                    _ => return Err(error(format!("unsupported git reference `{}`", query))),
+                };
+                Some(SourceKind::Git(reference))
+            }
+            (_, Some(_)) => return Err(error("only `git+` URLs can have a query".into())),
+        };
+
+        let (name, version) = match fragment {
+            Some(fragment) if fragment.contains(['@', ':']) => {
+                parse_name_and_version(fragment).map_err(error)?
+            }
+            Some(fragment) if fragment.starts_with(|c: char| c.is_ascii_digit()) => {
+                check_version(fragment).map_err(error)?;
+                (last_segment(url), Some(fragment.to_string()))
+            }
+            Some(fragment) => parse_name_and_version(fragment).map_err(error)?,
+            None => (last_segment(url), None),
+        };
+        if !is_valid_name(&name) {
+            return Err(error(format!(
+                "`{}` is not a valid package name, add it after `#`",
+                name
+            )));
+        }
+        Ok(Self {
+            name,
+            version,
+            url: Some(url.to_string()),
+            kind,
+            original: Some(spec.to_string()),
+        })
+    }
+}
+
+impl fmt::Display for PackageIdSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let url = match self.url {
+            Some(ref url) => url,
+            None => {
+                f.write_str(&self.name)?;
+                if let Some(ref version) = self.version {
+                    write!(f, "@{}", version)?;
+                }
+                return Ok(());
+            }
+        };
+        match self.kind {
+            Some(SourceKind::Git(_)) => f.write_str("git+")?,
+            Some(SourceKind::Path) => f.write_str("path+")?,
+            Some(SourceKind::Registry) => f.write_str("registry+")?,
+            Some(SourceKind::SparseRegistry) => f.write_str("sparse+")?,
+            None => {}
+        }
+        f.write_str(url)?;
+        match self.kind {
+            Some(SourceKind::Git(GitReference::Branch(ref branch))) => {
+                write!(f, "?branch={}", branch)?
+            }
+            Some(SourceKind::Git(GitReference::Tag(ref tag))) => write!(f, "?tag={}", tag)?,
+            Some(SourceKind::Git(GitReference::Rev(ref rev))) => write!(f, "?rev={}", rev)?,
+            _ => {}
+        }
+        match (self.name == last_segment(url), &self.version) {
+            (true, Some(version)) => write!(f, "#{}", version),
+            (true, None) => Ok(()),
+            (false, Some(version)) => write!(f, "#{}@{}", self.name, version),
+            (false, None) => write!(f, "#{}", self.name),
+        }
+    }
+}
+
+impl Serialize for PackageIdSpec {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.as_written())
+    }
+}
+
+impl<'de> Deserialize<'de> for PackageIdSpec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+/// `name`, `name@version` or `name:version`
+fn parse_name_and_version(s: &str) -> Result<(String, Option<String>), String> {
+    let (name, version) = match s.split_once(['@', ':']) {
+        Some((name, version)) => {
+            check_version(version)?;
+            (name, Some(version.to_string()))
+        }
+        None => (s, None),
+    };
+    if !is_valid_name(name) {
+        return Err(format!("`{}` is not a valid package name", name));
+    }
+    Ok((name.to_string(), version))
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let is_name_char = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
+    !name.is_empty() && name.chars().all(is_name_char)
+}
+
+/// A full semver version, or a prefix of one without pre-release and build metadata
+/// (`1` or `1.2`).
+fn check_version(version: &str) -> Result<(), String> {
+    let core = version.split(['-', '+']).next().unwrap_or_default();
+    let parts: Vec<&str> = core.split('.').collect();
+    let numeric = parts
+        .iter()
+        .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+    let complete = parts.len() == 3 || core == version;
+    if !numeric || parts.len() > 3 || !complete {
+        return Err(format!("`{}` is not a valid version", version));
+    }
+    Ok(())
+}
+
+/// Whether `version` matches the version of a spec, which may omit the minor and patch parts.
+fn version_matches(spec_version: &str, version: &str) -> bool {
+    if spec_version == version {
+        return true;
+    }
+    if spec_version.contains(['-', '+']) {
+        return false;
+    }
+    let core = version.split(['-', '+']).next().unwrap_or_default();
+    let spec_parts: Vec<&str> = spec_version.split('.').collect();
+    let parts: Vec<&str> = core.split('.').collect();
+    spec_parts.len() < parts.len() && parts.starts_with(&spec_parts)
+}
+
+fn last_segment(url: &str) -> String {
+    let path = url.trim_end_matches('/');
+    path.rsplit('/').next().unwrap_or_default().to_string()
+}
@@ -0,0 +1,100 @@
+use crate::afs::normalize;
+use crate::{AbstractFilesystem, MemoryFilesystem};
+use flate2::read::GzDecoder;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+/// Files of a gzipped `.crate` archive, as published to a registry.
+///
+/// Every file of a `.crate` archive is in a `name-version/` directory, which is stripped,
+/// so paths are relative to the package root like with `Filesystem`.
+/// The archive is decompressed into memory, nothing is written to disk.
+#[derive(Debug, Clone)]
+pub struct TarballFilesystem {
+    prefix: String,
+    files: MemoryFilesystem,
+}
+
+impl TarballFilesystem {
+    /// Read a `.crate` file, e.g. from `~/.cargo/registry/cache`.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(File::open(path)?)
+    }
+
+    /// Read a gzipped tar archive.
+    ///
+    /// Fails if the files of the archive are not all in the same top-level directory.
+    pub fn new(gzipped_tar: impl Read) -> io::Result<Self> {
+        let mut archive = tar::Archive::new(GzDecoder::new(gzipped_tar));
+        let mut prefix: Option<String> = None;
+        let mut files = MemoryFilesystem::new();
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let path = entry.path()?.into_owned();
+            let mut components = path.components();
+            let (top, rel_path) = match components.next() {
+                Some(Component::Normal(top)) => (top.to_string_lossy(), components.as_path()),
+                _ => return Err(invalid_path(&path)),
+            };
+            let rel_path = match normalize(rel_path) {
+                Some(rel_path) if !rel_path.is_empty() => rel_path,
+                _ => return Err(invalid_path(&path)),
+            };
+            match prefix {
+                Some(ref prefix) if *prefix != top => return Err(invalid_path(&path)),
+                Some(_) => {}
+                None => prefix = Some(top.into_owned()),
+            }
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents)?;
+            files.try_insert(rel_path, contents)?;
+        }
+        Ok(Self {
+            prefix: prefix.unwrap_or_default(),
+            files,
+        })
+    }
+
+    /// The top-level directory that was stripped from the paths, e.g. `serde-1.0.100`.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Paths and contents of all files, without the prefix.
+    pub fn files(&self) -> &BTreeMap<PathBuf, Vec<u8>> {
+        self.files.files()
+    }
+}
+
+fn invalid_path(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "{} is not in the `name-version/` directory of the archive",
+            path.display()
+        ),
+    )
+}
+
+impl AbstractFilesystem for TarballFilesystem {
+    fn file_names_in(&self, rel_path: &str) -> io::Result<BTreeSet<Box<str>>> {
+        self.files.file_names_in(rel_path)
+    }
+
+    fn read_file(&self, rel_path: &str) -> io::Result<Vec<u8>> {
+        self.files.read_file(rel_path)
+    }
+
+    fn exists(&self, rel_path: &str) -> bool {
+        self.files.exists(rel_path)
+    }
+
+    fn is_dir(&self, rel_path: &str) -> bool {
+        self.files.is_dir(rel_path)
+    }
+}
@@ -0,0 +1,253 @@
+use crate::{AbstractFilesystem, Edition, Product};
+use std::fmt;
+use std::path::Path;
+
+/// The kinds of products that are listed in arrays of tables, e.g. `[[bin]]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ProductKind {
+    Bin,
+    Example,
+    Test,
+    Bench,
+}
+
+impl ProductKind {
+    /// Name of the TOML table for this kind of product, e.g. `bin`.
+    pub fn table_name(self) -> &'static str {
+        match self {
+            ProductKind::Bin => "bin",
+            ProductKind::Example => "example",
+            ProductKind::Test => "test",
+            ProductKind::Bench => "bench",
+        }
+    }
+
+    /// Directory Cargo discovers products of this kind in.
+    fn dir(self) -> &'static str {
+        match self {
+            ProductKind::Bin => "src/bin",
+            ProductKind::Example => "examples",
+            ProductKind::Test => "tests",
+            ProductKind::Bench => "benches",
+        }
+    }
+}
+
+/// A problem found while inferring products from files, which Cargo would warn about or
+/// reject. Returned by `Manifest::complete_from_abstract_filesystem`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InferenceWarning {
+    /// More than one file would be discovered as the product `name`, e.g. both
+    /// `src/bin/foo.rs` and `src/bin/foo/main.rs`. Only the first of `paths` is used.
+    AmbiguousFiles {
+        kind: ProductKind,
+        name: String,
+        paths: Vec<String>,
+    },
+    /// A declared product has the name of a discovered one, but a different path,
+    /// so the discovered file is not used.
+    Shadowed {
+        kind: ProductKind,
+        name: String,
+        declared_path: String,
+        discovered_path: String,
+    },
+    /// A discovered file's name is not a valid product name, so it is skipped.
+    InvalidName {
+        kind: ProductKind,
+        name: String,
+        path: String,
+    },
+    /// A product is declared with a `path` to a `main.rs` file, but without a `name`.
+    /// It was named after its directory, or after the package for `src/main.rs`.
+    UnnamedMain {
+        kind: ProductKind,
+        path: String,
+        name: String,
+    },
+}
+
+impl fmt::Display for InferenceWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InferenceWarning::AmbiguousFiles { kind, name, paths } => {
+                write!(
+                    f,
+                    "found more than one file for {} `{}`: {}",
+                    kind.table_name(),
+                    name,
+                    paths.join(", ")
+                )?;
+                match paths.first() {
+                    Some(path) => write!(f, "; using `{}`", path),
+                    None => Ok(()),
+                }
+            }
+            InferenceWarning::Shadowed {
+                kind,
+                name,
+                declared_path,
+                discovered_path,
+            } => write!(
+                f,
+                "{} `{}` is declared with path `{}`, so `{}` is not used",
+                kind.table_name(),
+                name,
+                declared_path,
+                discovered_path
+            ),
+            InferenceWarning::InvalidName { kind, name, path } => write!(
+                f,
+                "`{}` is not a valid {} name, so `{}` is skipped",
+                name,
+                kind.table_name(),
+                path
+            ),
+            InferenceWarning::UnnamedMain { kind, path, name } => write!(
+                f,
+                "{} with path `{}` has no name, assuming `{}`",
+                kind.table_name(),
+                path,
+                name
+            ),
+        }
+    }
+}
+
+/// Fill in the `name` and `path` of explicitly declared products where they are missing,
+/// and add the products discovered on the filesystem if `autodiscover` is set, the way Cargo
+/// does it.
+///
+/// A discovered product is skipped if a declared one has the same name or path.
+/// `edition` is the edition of the package, which discovered products are built with.
+pub(crate) fn complete_products(
+    products: &mut Option<Vec<Product>>,
+    kind: ProductKind,
+    autodiscover: bool,
+    package_name: &str,
+    edition: Option<&Edition>,
+    fs: &dyn AbstractFilesystem,
+    warnings: &mut Vec<InferenceWarning>,
+) {
+    let mut discovered = Vec::new();
+    if kind == ProductKind::Bin && has_file(fs, "src", "main.rs") {
+        discovered.push((package_name.to_string(), "src/main.rs".to_string()));
+    }
+    discovered.extend(discover(kind, fs, warnings));
+
+    for product in products.iter_mut().flatten() {
+        match (&product.name, &product.path) {
+            (Some(name), None) => {
+                let path = match discovered.iter().find(|(n, _)| n == name) {
+                    Some((_, path)) => path.clone(),
+                    None => format!("{}/{}.rs", kind.dir(), name),
+                };
+                product.path = Some(path);
+            }
+            (None, Some(path)) => {
+                let name = name_from_path(path, package_name);
+                if path.ends_with("main.rs") {
+                    warnings.push(InferenceWarning::UnnamedMain {
+                        kind,
+                        path: path.clone(),
+                        name: name.clone(),
+                    });
+                }
+                product.name = Some(name);
+            }
+            _ => {}
+        }
+    }
+
+    if !autodiscover {
+        return;
+    }
+    let products = products.get_or_insert_with(Vec::new);
+    for (name, path) in discovered {
+        let same_path = products.iter().any(|p| p.path.as_ref() == Some(&path));
+        let same_name = products.iter().find(|p| p.name.as_ref() == Some(&name));
+        match (same_path, same_name) {
+            (true, _) => {}
+            (false, Some(declared)) => warnings.push(InferenceWarning::Shadowed {
+                kind,
+                name,
+                declared_path: declared.path.clone().unwrap_or_default(),
+                discovered_path: path,
+            }),
+            (false, None) => products.push(Product {
+                name: Some(name),
+                path: Some(path),
+                edition: edition.cloned(),
+                ..Product::default()
+            }),
+        }
+    }
+}
+
+/// `dir/<name>.rs` and `dir/<name>/main.rs`, as `(name, path)`.
+fn discover(
+    kind: ProductKind,
+    fs: &dyn AbstractFilesystem,
+    warnings: &mut Vec<InferenceWarning>,
+) -> Vec<(String, String)> {
+    let dir = kind.dir();
+    let mut found: Vec<(String, Vec<String>)> = Vec::new();
+    for file_name in fs.file_names_in(dir).unwrap_or_default() {
+        let rel_path = format!("{}/{}", dir, file_name);
+        let (name, path) = if let Some(name) = file_name.strip_suffix(".rs") {
+            (name.to_string(), rel_path)
+        } else if has_file(fs, &rel_path, "main.rs") {
+            (file_name.to_string(), rel_path + "/main.rs")
+        } else {
+            continue;
+        };
+        if !is_valid_name(&name) {
+            warnings.push(InferenceWarning::InvalidName { kind, name, path });
+            continue;
+        }
+        // `foo.rs` and `foo/main.rs` are both discovered as `foo`
+        match found.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, paths)) => paths.push(path),
+            None => found.push((name, vec![path])),
+        }
+    }
+
+    let mut out = Vec::new();
+    for (name, mut paths) in found {
+        if paths.len() > 1 {
+            warnings.push(InferenceWarning::AmbiguousFiles {
+                kind,
+                name: name.clone(),
+                paths: paths.clone(),
+            });
+        }
+        out.push((name, paths.swap_remove(0)));
+    }
+    out
+}
+
+/// Name of a product declared with only a `path`: the file name, or the directory name
+/// for `<name>/main.rs`. `src/main.rs` is named after the package.
+fn name_from_path(path: &str, package_name: &str) -> String {
+    let path = Path::new(path);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    if stem != "main" {
+        return stem.into_owned();
+    }
+    match path.parent().and_then(Path::file_name) {
+        Some(dir) if dir != "src" => dir.to_string_lossy().into_owned(),
+        _ => package_name.to_string(),
+    }
+}
+
+/// Cargo allows `-` in product names, which is replaced with `_` for the crate name.
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+pub(crate) fn has_file(fs: &dyn AbstractFilesystem, dir: &str, file_name: &str) -> bool {
+    fs.file_names_in(dir)
+        .is_ok_and(|names| names.contains(file_name))
+}
@@ -0,0 +1,236 @@
+use crate::{Edition, FeatureGraph, Manifest, MaybeInherited, Platform, Product};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Crate types accepted by Cargo in `crate-type`.
+const CRATE_TYPES: &[&str] = &[
+    "bin",
+    "lib",
+    "rlib",
+    "dylib",
+    "cdylib",
+    "staticlib",
+    "proc-macro",
+];
+
+/// crates.io rejects packages with more keywords or categories than this.
+const MAX_KEYWORDS: usize = 5;
+const MAX_CATEGORIES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    /// Cargo accepts the manifest, but crates.io would reject it on publish.
+    Warning,
+    /// Cargo would reject the manifest.
+    Error,
+}
+
+/// A problem found by `Manifest::validate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Stable identifier of the rule that was violated, e.g. `too-many-keywords`.
+    pub code: &'static str,
+    /// Dotted path of the offending key, e.g. `bin.1.name`.
+    pub key: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn error(code: &'static str, key: String, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            code,
+            key,
+            message,
+        }
+    }
+
+    fn warning(code: &'static str, key: String, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(code, key, message)
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(
+            f,
+            "{}[{}]: {}: {}",
+            severity, self.code, self.key, self.message
+        )
+    }
+}
+
+impl<Metadata> Manifest<Metadata> {
+    /// Check the manifest for problems that Cargo (or crates.io, on publish) would reject,
+    /// even though it parses fine. See `Severity` for which ones are errors.
+    ///
+    /// Products are only checked as far as they are known, so call `complete_from_path`
+    /// first to include the auto-discovered ones.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let graph = FeatureGraph::build(self);
+        let features = graph.features();
+
+        if let Some(ref package) = self.package {
+            let lists = [
+                (
+                    "too-many-keywords",
+                    "keywords",
+                    &package.keywords,
+                    MAX_KEYWORDS,
+                ),
+                (
+                    "too-many-categories",
+                    "categories",
+                    &package.categories,
+                    MAX_CATEGORIES,
+                ),
+            ];
+            for (code, key, list, max) in lists {
+                if let Some(MaybeInherited::Local(list)) = list {
+                    if list.len() > max {
+                        diagnostics.push(Diagnostic::warning(
+                            code,
+                            format!("package.{}", key),
+                            format!("at most {} {} are allowed, found {}", max, key, list.len()),
+                        ));
+                    }
+                }
+            }
+
+            if let Some(MaybeInherited::Local(edition)) = package.edition {
+                check_edition(edition, "package.edition", &mut diagnostics);
+            }
+
+            if let (Some(default_run), Some(bins)) = (&package.default_run, &self.bin) {
+                if !bins
+                    .iter()
+                    .any(|bin| bin.name.as_ref() == Some(default_run))
+                {
+                    diagnostics.push(Diagnostic::error(
+                        "unknown-default-run",
+                        "package.default-run".into(),
+                        format!("`{}` is not the name of a binary", default_run),
+                    ));
+                }
+            }
+        }
+
+        let workspace_edition = self
+            .workspace
+            .as_ref()
+            .and_then(|w| w.package.as_ref())
+            .and_then(|p| p.edition);
+        if let Some(edition) = workspace_edition {
+            check_edition(edition, "workspace.package.edition", &mut diagnostics);
+        }
+
+        if let Some(ref lib) = self.lib {
+            check_crate_types(lib, "lib", &mut diagnostics);
+            if let Some(edition) = lib.edition {
+                check_edition(edition, "lib.edition", &mut diagnostics);
+            }
+        }
+        let products = [
+            ("bin", &self.bin),
+            ("example", &self.example),
+            ("test", &self.test),
+            ("bench", &self.bench),
+        ];
+        for (kind, products) in products {
+            let mut names = BTreeSet::new();
+            for (i, product) in products.iter().flatten().enumerate() {
+                let key = format!("{}.{}", kind, i);
+                if let Some(ref name) = product.name {
+                    if !names.insert(name) {
+                        diagnostics.push(Diagnostic::error(
+                            "duplicate-product-name",
+                            format!("{}.name", key),
+                            format!("there is more than one `{}` named `{}`", kind, name),
+                        ));
+                    }
+                }
+                for feature in &product.required_features {
+                    // `dep/feature` refers to a feature of a dependency
+                    if !feature.contains('/') && !features.contains_key(feature) {
+                        diagnostics.push(Diagnostic::error(
+                            "unknown-required-feature",
+                            format!("{}.required-features", key),
+                            format!("feature `{}` is not defined", feature),
+                        ));
+                    }
+                }
+                check_crate_types(product, &key, &mut diagnostics);
+                if let Some(edition) = product.edition {
+                    check_edition(edition, &format!("{}.edition", key), &mut diagnostics);
+                }
+            }
+        }
+
+        for platform in self.target.iter().flatten().map(|(platform, _)| platform) {
+            if let Err(err) = platform.parse::<Platform>() {
+                diagnostics.push(Diagnostic::error(
+                    "invalid-target",
+                    format!("target.{}", platform),
+                    err.to_string(),
+                ));
+            }
+        }
+
+        // `lib` and `target` only have a meaning for artifact dependencies, Cargo fails with
+        // "'lib'/'target' specifier cannot be used without an 'artifact = …' value" otherwise.
+        for entry in self.all_dependencies() {
+            let detail = match entry.dep.detail() {
+                Some(detail) if detail.artifact.is_none() => detail,
+                _ => continue,
+            };
+            let keys = [
+                ("lib", detail.lib.is_some()),
+                ("target", detail.target.is_some()),
+            ];
+            for (key, _) in keys.iter().filter(|(_, is_set)| *is_set) {
+                diagnostics.push(Diagnostic::error(
+                    "artifact-key-without-artifact",
+                    format!("{}.{}", entry.key(), key),
+                    format!("`{}` can only be used together with `artifact`", key),
+                ));
+            }
+        }
+
+        diagnostics
+    }
+}
+
+fn check_crate_types(product: &Product, key: &str, diagnostics: &mut Vec<Diagnostic>) {
+    for crate_type in product.crate_type.iter().flatten() {
+        if !CRATE_TYPES.contains(&crate_type.as_str()) {
+            diagnostics.push(Diagnostic::error(
+                "invalid-crate-type",
+                format!("{}.crate-type", key),
+                format!(
+                    "`{}` is not a valid crate type, expected one of: {}",
+                    crate_type,
+                    CRATE_TYPES.join(", ")
+                ),
+            ));
+        }
+    }
+}
+
+fn check_edition(edition: Edition, key: &str, diagnostics: &mut Vec<Diagnostic>) {
+    if let Edition::Unknown(year) = edition {
+        diagnostics.push(Diagnostic::error(
+            "unknown-edition",
+            key.to_string(),
+            format!("edition {} is not known", year),
+        ));
+    }
+}
@@ -0,0 +1,663 @@
+use crate::afs::{normalize, SubFilesystem};
+use crate::dependencies::simplify;
+use crate::{
+    AbstractFilesystem, Dependency, DependencyDetail, DepsSet, Edition, Error, Manifest,
+    MaybeInherited, Resolver, StringOrBool, Workspace,
+};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+impl Workspace {
+    /// Find and load all members of this workspace, like Cargo does.
+    ///
+    /// `fs` must be rooted at the workspace root. Glob patterns in `members` (e.g. `crates/*`
+    /// or `tools/**`) are expanded, and directories matched by a glob are skipped if they are
+    /// inside one of the `exclude` paths or don't contain a `Cargo.toml`. Members listed without
+    /// a glob are never excluded, and it's an error if they don't contain a `Cargo.toml`.
+    ///
+    /// Members are loaded with their inherited fields resolved against this workspace and
+    /// completed with `complete_from_abstract_filesystem`. They are keyed by their path
+    /// relative to the workspace root. If the workspace root's `Cargo.toml` has a `[package]`,
+    /// the root package is a member too, with the key `.`.
+    pub fn discover_members(
+        &self,
+        fs: &dyn AbstractFilesystem,
+    ) -> Result<BTreeMap<String, Manifest>, Error> {
+        let mut members = BTreeMap::new();
+        let root = Manifest::from_slice(&fs.read_file("Cargo.toml")?)?;
+        if root.package.is_some() {
+            members.insert(".".to_string(), self.load_member(fs, "")?);
+        }
+        for pattern in &self.members {
+            let is_glob = pattern.contains(['*', '?', '[']);
+            for path in expand_member_pattern(fs, pattern)? {
+                if is_glob && self.is_excluded(&path) {
+                    continue;
+                }
+                let has_manifest = SubFilesystem::new(fs, &path).exists("Cargo.toml");
+                if !has_manifest && is_glob {
+                    continue;
+                } else if !has_manifest {
+                    return Err(Error::WorkspaceIntegrity(format!(
+                        "workspace member `{}` does not contain a Cargo.toml",
+                        path
+                    )));
+                }
+                let manifest = self.load_member(fs, &path)?;
+                let key = if path.is_empty() {
+                    ".".to_string()
+                } else {
+                    path
+                };
+                members.insert(key, manifest);
+            }
+        }
+        Ok(members)
+    }
+
+    /// The members Cargo builds when no package is selected, from those returned by
+    /// `discover_members`.
+    ///
+    /// That's the members matching `default-members` if it's set, otherwise the root package,
+    /// or all members if the workspace is virtual. It's an error if an entry of
+    /// `default-members` matches no member.
+    pub fn discover_default_members(
+        &self,
+        fs: &dyn AbstractFilesystem,
+    ) -> Result<BTreeMap<String, Manifest>, Error> {
+        let mut members = self.discover_members(fs)?;
+        let default_members = match self.default_members {
+            Some(ref default_members) => default_members,
+            None if members.contains_key(".") => {
+                members.retain(|path, _| path == ".");
+                return Ok(members);
+            }
+            None => return Ok(members),
+        };
+        let mut selected = BTreeMap::new();
+        for pattern in default_members {
+            let pattern = match pattern.trim_start_matches("./").trim_end_matches('/') {
+                "" => ".",
+                pattern => pattern,
+            };
+            let matcher = glob::Pattern::new(pattern).map_err(|err| {
+                Error::WorkspaceIntegrity(format!(
+                    "invalid workspace default member pattern `{}`: {}",
+                    pattern, err
+                ))
+            })?;
+            let options = glob::MatchOptions {
+                require_literal_separator: true,
+                ..glob::MatchOptions::new()
+            };
+            let matching: Vec<String> = members
+                .keys()
+                .filter(|path| *path == pattern || matcher.matches_with(path, options))
+                .cloned()
+                .collect();
+            if matching.is_empty() {
+                return Err(Error::WorkspaceIntegrity(format!(
+                    "workspace default member `{}` is not a member of the workspace",
+                    pattern
+                )));
+            }
+            for path in matching {
+                if let Some(manifest) = members.remove(&path) {
+                    selected.insert(path, manifest);
+                }
+            }
+        }
+        Ok(selected)
+    }
+
+    /// Load the member in `path`, relative to the workspace root of `fs`.
+    fn load_member(&self, fs: &dyn AbstractFilesystem, path: &str) -> Result<Manifest, Error> {
+        let member_fs = SubFilesystem::new(fs, path);
+        let mut manifest = Manifest::from_slice(&member_fs.read_file("Cargo.toml")?)?;
+        let workspace_dir: PathBuf = Path::new(path).components().map(|_| "..").collect();
+        manifest.inherit_workspace(self, &workspace_dir)?;
+        manifest.complete_from_abstract_filesystem(&member_fs)?;
+        Ok(manifest)
+    }
+
+    fn is_excluded(&self, path: &str) -> bool {
+        self.exclude.iter().flatten().any(|excluded| {
+            Path::new(path).starts_with(excluded.trim_start_matches("./").trim_end_matches('/'))
+        })
+    }
+}
+
+/// Expand a `members` entry into paths relative to the workspace root. Components with
+/// wildcards only match directories; `**` matches any number of nested directories.
+fn expand_member_pattern(fs: &dyn AbstractFilesystem, pattern: &str) -> Result<Vec<String>, Error> {
+    let is_glob = pattern.contains(['*', '?', '[']);
+    let mut paths = vec![String::new()];
+    for component in pattern.split('/').filter(|c| !c.is_empty() && *c != ".") {
+        let mut expanded = Vec::new();
+        for base in paths {
+            if component == "**" {
+                collect_dirs(fs, base, 0, &mut expanded);
+            } else if component.contains(['*', '?', '[']) {
+                let matcher = glob::Pattern::new(component).map_err(|err| {
+                    Error::WorkspaceIntegrity(format!(
+                        "invalid workspace member pattern `{}`: {}",
+                        pattern, err
+                    ))
+                })?;
+                for name in fs.file_names_in(dir_path(&base)).unwrap_or_default().iter() {
+                    let path = join(&base, name);
+                    if matcher.matches(name) && fs.is_dir(&path) {
+                        expanded.push(path);
+                    }
+                }
+            } else {
+                let path = join(&base, component);
+                if !is_glob || fs.is_dir(&path) {
+                    expanded.push(path);
+                }
+            }
+        }
+        paths = expanded;
+    }
+    Ok(paths)
+}
+
+/// `**` doesn't match directories nested deeper than this, so a symlink to a parent
+/// directory can't make `collect_dirs` recurse forever.
+const MAX_GLOB_DEPTH: usize = 16;
+
+/// `dir` and all directories nested in it, up to `MAX_GLOB_DEPTH` levels deep.
+fn collect_dirs(fs: &dyn AbstractFilesystem, dir: String, depth: usize, out: &mut Vec<String>) {
+    let names = fs.file_names_in(dir_path(&dir)).unwrap_or_default();
+    out.push(dir.clone());
+    if depth >= MAX_GLOB_DEPTH {
+        return;
+    }
+    for name in names.iter() {
+        let path = join(&dir, name);
+        if fs.is_dir(&path) {
+            collect_dirs(fs, path, depth + 1, out);
+        }
+    }
+}
+
+fn dir_path(path: &str) -> &str {
+    if path.is_empty() {
+        "."
+    } else {
+        path
+    }
+}
+
+fn join(base: &str, name: &str) -> String {
+    if base.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", base, name)
+    }
+}
+
+impl<Metadata> Manifest<Metadata> {
+    /// The dependency resolver Cargo uses for this package.
+    ///
+    /// The resolver is a setting of the whole workspace, so it's taken from the workspace root:
+    /// `workspace_root` is the root's manifest if this package is a member of a workspace,
+    /// or `None` if this manifest is the root itself (or a package without a workspace).
+    /// A member's own `package.resolver` is ignored, like Cargo does.
+    ///
+    /// For the root that's `package.resolver` if set, then `workspace.resolver`, then the
+    /// default for the package's edition (`Resolver::default_for_edition`). A virtual workspace
+    /// without `resolver` uses `Resolver::V1`.
+    pub fn effective_resolver(&self, workspace_root: Option<&Self>) -> Resolver {
+        let root = workspace_root.unwrap_or(self);
+        let package = root.package.as_ref();
+        let workspace = root.workspace.as_ref();
+        if let Some(resolver) = package.and_then(|p| p.resolver) {
+            return resolver;
+        }
+        if let Some(resolver) = workspace.and_then(|w| w.resolver) {
+            return resolver;
+        }
+        match package.map(|p| &p.edition) {
+            Some(Some(MaybeInherited::Local(edition))) => Resolver::default_for_edition(edition),
+            Some(Some(MaybeInherited::Inherited { .. })) => workspace
+                .and_then(|w| w.package.as_ref())
+                .and_then(|p| p.edition.as_ref())
+                .map_or(Resolver::V1, Resolver::default_for_edition),
+            Some(None) | None => Resolver::V1,
+        }
+    }
+
+    /// Replace every `{ workspace = true }` field of `[package]` with the value defined in
+    /// the workspace root's `[workspace.package]` table, and every `{ workspace = true }`
+    /// dependency with the entry from `[workspace.dependencies]`
+    /// (see `inherit_workspace_dependencies`). `lints.workspace = true` is replaced with
+    /// `[workspace.lints]`.
+    ///
+    /// `workspace_dir` is the directory of the workspace root, relative to the directory of
+    /// this manifest (e.g. `../..`). It is used to rebase `readme` and `license-file`,
+    /// which are relative to the workspace root in `[workspace.package]`.
+    ///
+    /// Returns `Error::InheritedUnknownValue` if the workspace does not define an inherited key.
+    pub fn inherit_workspace(
+        &mut self,
+        workspace: &Workspace,
+        workspace_dir: &Path,
+    ) -> Result<(), Error> {
+        inherit(&mut self.lints, "lints", workspace.lints.as_ref())?;
+
+        let package = match self.package {
+            Some(ref mut package) => package,
+            None => return Ok(()),
+        };
+        let ws = workspace.package.as_ref();
+
+        package
+            .version
+            .inherit("version", ws.and_then(|w| w.version.as_ref()))?;
+        inherit(
+            &mut package.edition,
+            "edition",
+            ws.and_then(|w| w.edition.as_ref()),
+        )?;
+        inherit(
+            &mut package.authors,
+            "authors",
+            ws.and_then(|w| w.authors.as_ref()),
+        )?;
+        inherit(
+            &mut package.description,
+            "description",
+            ws.and_then(|w| w.description.as_ref()),
+        )?;
+        inherit(
+            &mut package.homepage,
+            "homepage",
+            ws.and_then(|w| w.homepage.as_ref()),
+        )?;
+        inherit(
+            &mut package.documentation,
+            "documentation",
+            ws.and_then(|w| w.documentation.as_ref()),
+        )?;
+        let readme = ws
+            .and_then(|w| w.readme.as_ref())
+            .map(|readme| match readme {
+                StringOrBool::String(path) => StringOrBool::String(rebase(workspace_dir, path)),
+                StringOrBool::Bool(flag) => StringOrBool::Bool(*flag),
+            });
+        inherit(&mut package.readme, "readme", readme.as_ref())?;
+        inherit(
+            &mut package.keywords,
+            "keywords",
+            ws.and_then(|w| w.keywords.as_ref()),
+        )?;
+        inherit(
+            &mut package.categories,
+            "categories",
+            ws.and_then(|w| w.categories.as_ref()),
+        )?;
+        inherit(
+            &mut package.license,
+            "license",
+            ws.and_then(|w| w.license.as_ref()),
+        )?;
+        let license_file = ws
+            .and_then(|w| w.license_file.as_deref())
+            .map(|path| rebase(workspace_dir, path));
+        inherit(
+            &mut package.license_file,
+            "license-file",
+            license_file.as_ref(),
+        )?;
+        inherit(
+            &mut package.repository,
+            "repository",
+            ws.and_then(|w| w.repository.as_ref()),
+        )?;
+        inherit(
+            &mut package.rust_version,
+            "rust-version",
+            ws.and_then(|w| w.rust_version.as_ref()),
+        )?;
+        inherit(
+            &mut package.exclude,
+            "exclude",
+            ws.and_then(|w| w.exclude.as_ref()),
+        )?;
+        inherit(
+            &mut package.include,
+            "include",
+            ws.and_then(|w| w.include.as_ref()),
+        )?;
+        inherit(
+            &mut package.publish,
+            "publish",
+            ws.and_then(|w| w.publish.as_ref()),
+        )?;
+
+        let no_dependencies = DepsSet::new();
+        self.inherit_workspace_dependencies(
+            workspace.dependencies.as_ref().unwrap_or(&no_dependencies),
+            workspace_dir,
+        )
+    }
+
+    /// Replace every dependency declared with `{ workspace = true }`, in all dependency tables
+    /// including `[target.*]` ones, with the entry of the same name in `[workspace.dependencies]`.
+    ///
+    /// Follows Cargo's rules: the member may only add `features` (which are merged with the
+    /// workspace ones), `optional` and `default-features`. `path`s of workspace dependencies are
+    /// rebased to be relative to this manifest; `workspace_dir` is the directory of the workspace
+    /// root, relative to the directory of this manifest.
+    ///
+    /// `default-features = false` has no effect if the workspace entry enables default features.
+    /// Before edition 2024 it's ignored, like Cargo does (with a warning); from edition 2024
+    /// it's an error.
+    pub fn inherit_workspace_dependencies(
+        &mut self,
+        workspace_dependencies: &DepsSet,
+        workspace_dir: &Path,
+    ) -> Result<(), Error> {
+        let edition = match self.package.as_ref().and_then(|p| p.edition.as_ref()) {
+            Some(MaybeInherited::Local(edition)) => *edition,
+            _ => Edition::default(),
+        };
+        for entry in self.all_dependencies_mut() {
+            if let Dependency::Detailed(ref detail) = entry.dep {
+                if detail.workspace == Some(true) {
+                    *entry.dep = inherit_dependency(
+                        entry.name,
+                        &entry.key(),
+                        detail,
+                        workspace_dependencies,
+                        workspace_dir,
+                        &edition,
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `key` is the dotted path of the dependency, e.g. `dev-dependencies.serde`.
+fn inherit_dependency(
+    name: &str,
+    key: &str,
+    local: &DependencyDetail,
+    workspace_dependencies: &DepsSet,
+    workspace_dir: &Path,
+    edition: &Edition,
+) -> Result<Dependency, Error> {
+    let local_only = [
+        ("version", local.version.is_some()),
+        ("registry", local.registry.is_some()),
+        ("registry-index", local.registry_index.is_some()),
+        ("path", local.path.is_some()),
+        ("git", local.git.is_some()),
+        ("branch", local.branch.is_some()),
+        ("tag", local.tag.is_some()),
+        ("rev", local.rev.is_some()),
+        ("package", local.package.is_some()),
+        ("artifact", local.artifact.is_some()),
+        ("target", local.target.is_some()),
+        ("lib", local.lib.is_some()),
+    ];
+    if let Some((key, _)) = local_only.iter().find(|(_, is_set)| *is_set) {
+        return Err(Error::InvalidDependency(format!(
+            "dependency `{}` is inherited from the workspace, so it cannot set `{}`",
+            name, key
+        )));
+    }
+
+    let mut detail = match workspace_dependencies.get(name) {
+        Some(Dependency::Simple(version)) => DependencyDetail {
+            version: Some(version.clone()),
+            ..DependencyDetail::default()
+        },
+        Some(Dependency::Detailed(detail)) => detail.clone(),
+        None => return Err(Error::InheritedUnknownValue(key.to_string())),
+    };
+    if detail.workspace.is_some() {
+        return Err(Error::InvalidDependency(format!(
+            "`workspace.dependencies.{}` cannot itself be inherited from a workspace",
+            name
+        )));
+    }
+    if detail.optional == Some(true) {
+        return Err(Error::InvalidDependency(format!(
+            "`workspace.dependencies.{}` is optional, but workspace dependencies cannot be optional",
+            name
+        )));
+    }
+
+    if let Some(ref features) = local.features {
+        let merged = detail.features.get_or_insert_with(Vec::new);
+        for feature in features {
+            if !merged.contains(feature) {
+                merged.push(feature.clone());
+            }
+        }
+    }
+    match (
+        detail.default_features.unwrap_or(true),
+        local.default_features,
+    ) {
+        // Cargo only warns about this before edition 2024, and keeps the defaults enabled
+        (true, Some(false)) if *edition >= Edition::E2024 => {
+            return Err(Error::InvalidDependency(format!(
+                "dependency `{}` sets `default-features = false`, but has no effect because \
+                 `workspace.dependencies.{}` enables default features",
+                name, name
+            )))
+        }
+        (false, Some(true)) => detail.default_features = Some(true),
+        _ => {}
+    }
+    detail.optional = local.optional;
+    detail.path = detail.path.map(|path| rebase(workspace_dir, &path));
+
+    Ok(simplify(detail))
+}
+
+impl<T: Clone> MaybeInherited<T> {
+    fn inherit(&mut self, key: &str, workspace_value: Option<&T>) -> Result<(), Error> {
+        if let MaybeInherited::Inherited { .. } = self {
+            let value =
+                workspace_value.ok_or_else(|| Error::InheritedUnknownValue(key.to_string()))?;
+            *self = MaybeInherited::Local(value.clone());
+        }
+        Ok(())
+    }
+}
+
+fn inherit<T: Clone>(
+    field: &mut Option<MaybeInherited<T>>,
+    key: &str,
+    workspace_value: Option<&T>,
+) -> Result<(), Error> {
+    match field {
+        Some(field) => field.inherit(key, workspace_value),
+        None => Ok(()),
+    }
+}
+
+/// Turn a path relative to the workspace root into a path relative to the member.
+fn rebase(workspace_dir: &Path, path: &str) -> String {
+    if workspace_dir.as_os_str().is_empty() {
+        path.to_string()
+    } else {
+        workspace_dir.join(path).to_string_lossy().into_owned()
+    }
+}
+
+/// Find the `[workspace]` that the package in `manifest_dir` belongs to.
+///
+/// `manifest_dir` is relative to the root of `fs`, and no directory above that root is searched.
+/// Returns the workspace root directory relative to `manifest_dir`, and its `[workspace]` table.
+/// An explicit `package.workspace` key is followed, otherwise parent directories are searched
+/// for the closest `Cargo.toml` with a `[workspace]` table that doesn't exclude the package.
+/// Like Cargo, it's an error if the package is not a member of the workspace found.
+pub(crate) fn find_workspace_root(
+    fs: &dyn AbstractFilesystem,
+    manifest_dir: &str,
+    package_workspace: Option<&str>,
+) -> Result<Option<(PathBuf, Workspace)>, Error> {
+    if let Some(explicit) = package_workspace {
+        let root_dir = PathBuf::from(explicit);
+        let workspace_dir =
+            normalize(&Path::new(manifest_dir).join(&root_dir)).ok_or_else(|| {
+                Error::WorkspaceIntegrity(format!(
+                    "`package.workspace` points to {}, which is outside of the filesystem",
+                    explicit
+                ))
+            })?;
+        let cargo_toml_path = join(&workspace_dir, "Cargo.toml");
+        let root = Manifest::from_slice(&fs.read_file(&cargo_toml_path)?)?;
+        let workspace = root.workspace.clone().ok_or_else(|| {
+            Error::WorkspaceIntegrity(format!(
+                "`package.workspace` points to {}, which has no `[workspace]` table",
+                cargo_toml_path
+            ))
+        })?;
+        let member_dir = relative_path(&workspace_dir, manifest_dir);
+        check_membership(&root, &workspace, &member_dir, &cargo_toml_path)?;
+        return Ok(Some((root_dir, workspace)));
+    }
+
+    let mut root_dir = PathBuf::new();
+    for dir in Path::new(manifest_dir).ancestors().skip(1) {
+        root_dir.push("..");
+        let cargo_toml_path = dir.join("Cargo.toml");
+        let cargo_toml_path = cargo_toml_path.to_string_lossy();
+        if !fs.exists(&cargo_toml_path) || fs.is_dir(&cargo_toml_path) {
+            continue;
+        }
+        let root = Manifest::from_slice(&fs.read_file(&cargo_toml_path)?)?;
+        let workspace = match root.workspace {
+            Some(ref workspace) => workspace.clone(),
+            None => continue,
+        };
+        let member_dir = relative_path(&dir.to_string_lossy(), manifest_dir);
+        if workspace.excludes(&member_dir) {
+            continue;
+        }
+        check_membership(&root, &workspace, &member_dir, &cargo_toml_path)?;
+        return Ok(Some((root_dir, workspace)));
+    }
+    Ok(None)
+}
+
+/// Like `find_workspace_root`, but for a package on disk, so parent directories are searched
+/// up to the filesystem root. `manifest_dir` may be relative to the current directory, and
+/// reported paths are based on it.
+pub(crate) fn find_workspace_root_on_disk(
+    manifest_dir: &Path,
+    package_workspace: Option<&str>,
+) -> Result<Option<(PathBuf, Workspace)>, Error> {
+    if let Some(explicit) = package_workspace {
+        let root_dir = PathBuf::from(explicit);
+        let workspace_dir = manifest_dir.join(&root_dir);
+        let cargo_toml_path = workspace_dir.join("Cargo.toml");
+        let root = Manifest::from_slice(&fs::read(&cargo_toml_path)?)?;
+        let workspace = root.workspace.clone().ok_or_else(|| {
+            Error::WorkspaceIntegrity(format!(
+                "`package.workspace` points to {}, which has no `[workspace]` table",
+                cargo_toml_path.display()
+            ))
+        })?;
+        let member_dir = relative_path(
+            &fs::canonicalize(&workspace_dir)?.to_string_lossy(),
+            &fs::canonicalize(manifest_dir)?.to_string_lossy(),
+        );
+        let cargo_toml_path = cargo_toml_path.display().to_string();
+        check_membership(&root, &workspace, &member_dir, &cargo_toml_path)?;
+        return Ok(Some((root_dir, workspace)));
+    }
+
+    let manifest_dir = fs::canonicalize(manifest_dir)?;
+    let mut root_dir = PathBuf::new();
+    for dir in manifest_dir.ancestors().skip(1) {
+        root_dir.push("..");
+        let cargo_toml_path = dir.join("Cargo.toml");
+        if !cargo_toml_path.is_file() {
+            continue;
+        }
+        let root = Manifest::from_slice(&fs::read(&cargo_toml_path)?)?;
+        let workspace = match root.workspace {
+            Some(ref workspace) => workspace.clone(),
+            None => continue,
+        };
+        let member_dir = relative_path(&dir.to_string_lossy(), &manifest_dir.to_string_lossy());
+        if workspace.excludes(&member_dir) {
+            continue;
+        }
+        let cargo_toml_path = cargo_toml_path.display().to_string();
+        check_membership(&root, &workspace, &member_dir, &cargo_toml_path)?;
+        return Ok(Some((root_dir, workspace)));
+    }
+    Ok(None)
+}
+
+impl Workspace {
+    /// Whether `path`, relative to the workspace root, is inside one of the `exclude` paths
+    /// and not listed in `members` explicitly.
+    fn excludes(&self, path: &str) -> bool {
+        let explicit_member = self
+            .members
+            .iter()
+            .any(|member| Path::new(path).starts_with(member.trim_start_matches("./")));
+        self.is_excluded(path) && !explicit_member
+    }
+}
+
+/// Cargo only accepts a workspace root for a package that is the root package itself,
+/// matches a `members` entry, or is a path dependency of the root package.
+fn check_membership(
+    root: &Manifest,
+    workspace: &Workspace,
+    member_dir: &str,
+    root_cargo_toml_path: &str,
+) -> Result<(), Error> {
+    let is_root = member_dir.is_empty() && root.package.is_some();
+    let is_listed = workspace.members.iter().any(|member| {
+        let member = member.trim_start_matches("./").trim_end_matches('/');
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..glob::MatchOptions::new()
+        };
+        member == member_dir
+            || glob::Pattern::new(member).is_ok_and(|p| p.matches_with(member_dir, options))
+    });
+    let is_path_dependency = root.package.is_some()
+        && root.all_dependencies().any(|entry| {
+            entry
+                .dep
+                .detail()
+                .and_then(|d| d.path.as_deref())
+                .and_then(|path| normalize(Path::new(path)))
+                .is_some_and(|path| path == member_dir)
+        });
+    if is_root || is_listed || is_path_dependency {
+        return Ok(());
+    }
+    Err(Error::WorkspaceIntegrity(format!(
+        "package in `{}` believes it's in the workspace of {}, but is not a member of it; \
+         add it to `workspace.members` or `workspace.exclude` there",
+        member_dir, root_cargo_toml_path
+    )))
+}
+
+/// `path` relative to `base`, where both are relative to the same directory.
+fn relative_path(base: &str, path: &str) -> String {
+    let base: Vec<_> = Path::new(base).components().collect();
+    let path: Vec<_> = Path::new(path).components().collect();
+    let common = base.iter().zip(&path).take_while(|(a, b)| a == b).count();
+    let ups = base[common..].iter().map(|_| "..".to_string());
+    let downs = path[common..]
+        .iter()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned());
+    ups.chain(downs).collect::<Vec<_>>().join("/")
+}
@@ -0,0 +1,295 @@
+use cargo_manifest::{DepKind, Dependency, DependencyDetail, ManifestDocument};
+use std::str::FromStr;
+
+const MANIFEST: &str = r#"# The package
+[package]
+name = "foo"
+version = "0.1.0" # bumped by CI
+
+[dependencies]
+# Keep serde first
+serde = { version = "1.0", features = ["derive"] }
+log = "0.4"
+
+[dependencies.tokio]
+version = "1"
+"#;
+
+#[test]
+fn untouched_document_round_trips() {
+    let doc = ManifestDocument::from_str(MANIFEST).unwrap();
+    assert_eq!(MANIFEST, doc.to_string());
+    assert_eq!("foo", doc.manifest().package.as_ref().unwrap().name);
+}
+
+#[test]
+fn set_version_keeps_comments() {
+    let mut doc = ManifestDocument::from_str(MANIFEST).unwrap();
+    assert!(doc.set_version("0.2.0").unwrap());
+    assert_eq!(MANIFEST.replace("\"0.1.0\"", "\"0.2.0\""), doc.to_string());
+    assert_eq!(
+        cargo_manifest::MaybeInherited::Local("0.2.0".to_string()),
+        doc.manifest().package.as_ref().unwrap().version
+    );
+}
+
+#[test]
+fn add_and_remove_dependencies() {
+    let mut doc = ManifestDocument::from_str(MANIFEST).unwrap();
+    doc.add_dependency(
+        DepKind::Normal,
+        None,
+        "log",
+        &Dependency::Simple("0.4.20".into()),
+    )
+    .unwrap();
+    doc.add_dependency(
+        DepKind::Dev,
+        None,
+        "rand",
+        &Dependency::Detailed(DependencyDetail {
+            version: Some("0.8".into()),
+            default_features: Some(false),
+            ..DependencyDetail::default()
+        }),
+    )
+    .unwrap();
+    assert!(doc
+        .remove_dependency(DepKind::Normal, None, "tokio")
+        .unwrap()
+        .is_some());
+    assert!(doc
+        .remove_dependency(DepKind::Build, None, "tokio")
+        .unwrap()
+        .is_none());
+
+    let expected = r#"# The package
+[package]
+name = "foo"
+version = "0.1.0" # bumped by CI
+
+[dependencies]
+# Keep serde first
+serde = { version = "1.0", features = ["derive"] }
+log = "0.4.20"
+
+[dev-dependencies]
+rand = { version = "0.8", default-features = false }
+"#;
+    assert_eq!(expected, doc.to_string());
+    assert!(doc.manifest().dev_dependencies.is_some());
+}
+
+#[test]
+fn toggle_dependency_features() {
+    let mut doc = ManifestDocument::from_str(MANIFEST).unwrap();
+    assert!(doc
+        .set_dependency_feature(DepKind::Normal, None, "serde", "rc", true)
+        .unwrap());
+    assert!(!doc
+        .set_dependency_feature(DepKind::Normal, None, "serde", "rc", true)
+        .unwrap());
+    assert!(doc
+        .set_dependency_feature(DepKind::Normal, None, "log", "std", true)
+        .unwrap());
+    assert!(doc
+        .set_dependency_feature(DepKind::Normal, None, "tokio", "full", true)
+        .unwrap());
+    assert!(doc
+        .set_dependency_feature(DepKind::Normal, None, "serde", "derive", false)
+        .unwrap());
+
+    let expected = r#"# The package
+[package]
+name = "foo"
+version = "0.1.0" # bumped by CI
+
+[dependencies]
+# Keep serde first
+serde = { version = "1.0", features = ["rc"] }
+log = { version = "0.4", features = ["std"] }
+
+[dependencies.tokio]
+version = "1"
+features = ["full"]
+"#;
+    assert_eq!(expected, doc.to_string());
+}
+
+#[test]
+fn add_dependency_to_inline_table() {
+    let mut doc = ManifestDocument::from_str(
+        "dependencies = { log = \"0.4\" }\n\n[package]\nname = \"foo\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+    doc.add_dependency(
+        DepKind::Normal,
+        None,
+        "log",
+        &Dependency::Simple("0.4.20".into()),
+    )
+    .unwrap();
+    doc.add_dependency(
+        DepKind::Normal,
+        None,
+        "rand",
+        &Dependency::Simple("0.8".into()),
+    )
+    .unwrap();
+    assert_eq!(
+        "dependencies = { log = \"0.4.20\", rand = \"0.8\" }\n\n[package]\nname = \"foo\"\nversion = \"0.1.0\"\n",
+        doc.to_string()
+    );
+    assert_eq!(2, doc.manifest().dependencies.as_ref().unwrap().len());
+}
+
+#[test]
+fn edits_match_manifest_methods() {
+    const WITH_FEATURES: &str = r#"[package]
+name = "foo"
+version = "0.1.0"
+
+[features]
+default = ["json", "dep:yaml"]
+json = ["dep:serde_json", "serde/derive"]
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+serde_json = { version = "1", optional = true }
+yaml = { version = "0.9", optional = true } # for configs
+"#;
+    let mut doc = ManifestDocument::from_str(WITH_FEATURES).unwrap();
+    let mut manifest = doc.manifest().clone();
+    let rc = Dependency::Detailed(DependencyDetail {
+        features: Some(vec!["rc".into()]),
+        ..DependencyDetail::default()
+    });
+    let libc = Dependency::Simple("0.2".into());
+    doc.add_dependency(DepKind::Normal, None, "serde", &rc)
+        .unwrap();
+    manifest.add_dependency(DepKind::Normal, None, "serde", &rc);
+    doc.add_dependency(DepKind::Normal, Some("cfg(unix)"), "libc", &libc)
+        .unwrap();
+    manifest.add_dependency(DepKind::Normal, Some("cfg(unix)"), "libc", &libc);
+    assert_eq!(&manifest, doc.manifest());
+
+    let expected = r#"[package]
+name = "foo"
+version = "0.1.0"
+
+[features]
+default = ["json", "dep:yaml"]
+json = ["dep:serde_json", "serde/derive"]
+
+[dependencies]
+serde = { version = "1.0", features = ["derive", "rc"] }
+serde_json = { version = "1", optional = true }
+yaml = { version = "0.9", optional = true } # for configs
+
+[target."cfg(unix)".dependencies]
+libc = "0.2"
+"#;
+    assert_eq!(expected, doc.to_string());
+
+    for (platform, name) in [(Some("cfg(unix)"), "libc"), (None, "serde_json")] {
+        assert_eq!(
+            manifest.remove_dependency(DepKind::Normal, platform, name),
+            doc.remove_dependency(DepKind::Normal, platform, name)
+                .unwrap()
+        );
+    }
+    assert_eq!(&manifest, doc.manifest());
+    assert_eq!(
+        r#"[package]
+name = "foo"
+version = "0.1.0"
+
+[features]
+default = ["json", "dep:yaml"]
+json = ["serde/derive"]
+
+[dependencies]
+serde = { version = "1.0", features = ["derive", "rc"] }
+yaml = { version = "0.9", optional = true } # for configs
+"#,
+        doc.to_string()
+    );
+}
+
+#[test]
+fn upgrade_dependencies() {
+    let toml = MANIFEST.replace(
+        "log = \"0.4\"",
+        "log = \"0.4\"\nlocal = { path = \"../local\" }\nshared = { workspace = true }",
+    );
+    let mut doc = ManifestDocument::from_str(&toml).unwrap();
+    let mut manifest = doc.manifest().clone();
+    for name in ["serde", "log", "tokio", "local"] {
+        assert!(doc
+            .set_dependency_version(DepKind::Normal, None, name, "2")
+            .unwrap());
+        assert!(manifest
+            .set_dependency_version(DepKind::Normal, None, name, "2")
+            .unwrap());
+    }
+    assert_eq!(&manifest, doc.manifest());
+    assert!(!doc
+        .set_dependency_version(DepKind::Dev, None, "serde", "2")
+        .unwrap());
+    assert!(doc
+        .set_dependency_version(DepKind::Normal, None, "shared", "2")
+        .is_err());
+    assert!(manifest
+        .set_dependency_version(DepKind::Normal, None, "shared", "2")
+        .is_err());
+
+    let expected = r#"# The package
+[package]
+name = "foo"
+version = "0.1.0" # bumped by CI
+
+[dependencies]
+# Keep serde first
+serde = { version = "2", features = ["derive"] }
+log = "2"
+local = { path = "../local", version = "2" }
+shared = { workspace = true }
+
+[dependencies.tokio]
+version = "2"
+"#;
+    assert_eq!(expected, doc.to_string());
+}
+
+#[test]
+fn disable_last_feature() {
+    let mut doc = ManifestDocument::from_str(
+        r#"[package]
+name = "foo"
+version = "0.1.0"
+
+[dependencies]
+serde = { version = "1", features = ["derive"] } # serialization
+rand = { version = "0.8", features = ["small_rng"], optional = true }
+log = { version = "0.4", optional = true, features = ["std"] }
+"#,
+    )
+    .unwrap();
+    for (name, feature) in [("serde", "derive"), ("rand", "small_rng"), ("log", "std")] {
+        assert!(doc
+            .set_dependency_feature(DepKind::Normal, None, name, feature, false)
+            .unwrap());
+    }
+    assert_eq!(
+        r#"[package]
+name = "foo"
+version = "0.1.0"
+
+[dependencies]
+serde = "1" # serialization
+rand = { version = "0.8", optional = true }
+log = { version = "0.4", optional = true }
+"#,
+        doc.to_string()
+    );
+}
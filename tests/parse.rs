@@ -1,5 +1,7 @@
 use cargo_manifest as lib;
+use cargo_manifest::AbstractFilesystem;
 use cargo_manifest::{Manifest, MaybeInherited, Publish};
+use std::collections::BTreeSet;
 use std::fs::read;
 use std::str::FromStr;
 
@@ -24,27 +26,10 @@ fn opt_level() {
     let package = m.package.as_ref().unwrap();
     assert_eq!("byteorder", package.name);
     let profiles = m.profile.unwrap();
+    assert_eq!(Some(lib::OptLevel::O3), profiles.bench.unwrap().opt_level);
     assert_eq!(
-        3,
-        profiles
-            .bench
-            .unwrap()
-            .opt_level
-            .unwrap()
-            .as_integer()
-            .unwrap()
-    );
-    assert_eq!(
-        2,
-        profiles
-            .custom
-            .get("my-custom")
-            .cloned()
-            .unwrap()
-            .opt_level
-            .unwrap()
-            .as_integer()
-            .unwrap()
+        Some(lib::OptLevel::O2),
+        profiles.custom.get("my-custom").cloned().unwrap().opt_level
     );
     assert!(!m.lib.unwrap().bench);
     assert_eq!(None, package.edition);
@@ -273,3 +258,1879 @@ tokio = { workspace = true, features = [ "rt-multi-thread" ] }
     )
     .unwrap();
 }
+
+/// Inherited package fields are replaced with the values from `[workspace.package]`.
+#[test]
+fn resolved_package_inheritance() {
+    let m = Manifest::from_path_resolved("tests/workspace/member/Cargo.toml")
+        .expect("load workspace member");
+    let package = m.package.as_ref().unwrap();
+    assert_eq!(MaybeInherited::Local("1.2.3".to_string()), package.version);
+    assert_eq!(
+        Some(MaybeInherited::Local(lib::Edition::E2021)),
+        package.edition
+    );
+    assert_eq!(
+        Some(MaybeInherited::Local(vec![
+            "Ferris <ferris@example.com>".to_string()
+        ])),
+        package.authors
+    );
+    assert_eq!(
+        Some(MaybeInherited::Local(lib::StringOrBool::String(
+            "../README.md".to_string()
+        ))),
+        package.readme
+    );
+    assert_eq!(Some(lib::Edition::E2021), m.lib.unwrap().edition);
+}
+
+#[test]
+fn resolved_package_inheritance_missing_key() {
+    let err = Manifest::from_path_resolved("tests/workspace/incomplete/Cargo.toml").unwrap_err();
+    assert!(matches!(err, lib::Error::InheritedUnknownValue(ref key) if key == "description"));
+
+    let err = Manifest::from_path_resolved("/").unwrap_err().to_string();
+    assert!(err.contains("/ is not a path to a file"), "{}", err);
+}
+
+/// Loading through `AbstractFilesystem` finds the workspace root within the filesystem.
+#[test]
+fn resolved_from_abstract_filesystem() {
+    let fs = lib::Filesystem::new(std::path::Path::new("tests/workspace"));
+    assert!(fs.exists("member/src/lib.rs"));
+    assert!(fs.is_dir("member/src"));
+    let m = Manifest::from_abstract_filesystem_resolved(&fs, "member").expect("load member");
+    let package = m.package.as_ref().unwrap();
+    assert_eq!(MaybeInherited::Local("1.2.3".to_string()), package.version);
+    assert_eq!(Some("src/lib.rs"), m.lib.unwrap().path.as_deref());
+
+    // The root is not searched above the filesystem
+    let fs = lib::Filesystem::new(std::path::Path::new("tests/workspace/member"));
+    let err = Manifest::from_abstract_filesystem_resolved(&fs, "").unwrap_err();
+    assert!(matches!(err, lib::Error::WorkspaceIntegrity(_)));
+}
+
+/// `exists` and `is_dir` default to checking directory listings.
+#[test]
+fn abstract_filesystem_defaults() {
+    struct Listing;
+    impl lib::AbstractFilesystem for Listing {
+        fn file_names_in(&self, rel_path: &str) -> std::io::Result<BTreeSet<Box<str>>> {
+            match rel_path {
+                "." => Ok(["Cargo.toml".into(), "src".into()].into()),
+                "src" => Ok(["main.rs".into()].into()),
+                _ => Err(std::io::ErrorKind::NotFound.into()),
+            }
+        }
+    }
+    assert!(Listing.exists("Cargo.toml"));
+    assert!(Listing.exists("src/main.rs"));
+    assert!(!Listing.exists("src/lib.rs"));
+    assert!(Listing.is_dir("src"));
+    assert!(!Listing.is_dir("src/main.rs"));
+    let err = Listing.read_file("Cargo.toml").unwrap_err();
+    assert_eq!(std::io::ErrorKind::Unsupported, err.kind());
+}
+
+/// Like Cargo, a workspace root is only used for packages that are its members.
+#[test]
+fn workspace_membership() {
+    let fs = lib::MemoryFilesystem::new()
+        .with_file(
+            "Cargo.toml",
+            r#"
+[package]
+name = "root"
+version = "0.1.0"
+
+[dependencies]
+helper = { path = "helper" }
+
+[workspace]
+members = ["crates/*"]
+exclude = ["crates/excluded"]
+
+[workspace.package]
+version = "2.0.0"
+"#,
+        )
+        .with_file(
+            "crates/listed/Cargo.toml",
+            "[package]\nname = \"listed\"\nversion.workspace = true\n",
+        )
+        .with_file(
+            "helper/Cargo.toml",
+            "[package]\nname = \"helper\"\nversion.workspace = true\n",
+        )
+        .with_file(
+            "crates/excluded/Cargo.toml",
+            "[package]\nname = \"excluded\"\nversion = \"0.1.0\"\n",
+        )
+        .with_file(
+            "other/Cargo.toml",
+            "[package]\nname = \"other\"\nversion = \"0.1.0\"\n",
+        )
+        .with_file(
+            "explicit/Cargo.toml",
+            "[package]\nname = \"explicit\"\nversion = \"0.1.0\"\nworkspace = \"..\"\n",
+        );
+
+    let listed = Manifest::from_abstract_filesystem_resolved(&fs, "crates/listed").unwrap();
+    assert_eq!(
+        MaybeInherited::Local("2.0.0".to_string()),
+        listed.package.unwrap().version
+    );
+    // Path dependencies of the root package are members
+    let helper = Manifest::from_abstract_filesystem_resolved(&fs, "helper").unwrap();
+    assert_eq!(
+        MaybeInherited::Local("2.0.0".to_string()),
+        helper.package.unwrap().version
+    );
+    // Excluded packages don't belong to any workspace
+    Manifest::from_abstract_filesystem_resolved(&fs, "crates/excluded").unwrap();
+
+    let err = Manifest::from_abstract_filesystem_resolved(&fs, "other").unwrap_err();
+    assert!(
+        matches!(err, lib::Error::WorkspaceIntegrity(ref msg) if msg.contains("`other`")),
+        "{}",
+        err
+    );
+    let err = Manifest::from_abstract_filesystem_resolved(&fs, "explicit").unwrap_err();
+    assert!(matches!(err, lib::Error::WorkspaceIntegrity(_)), "{}", err);
+}
+
+/// Dependencies with `workspace = true` are replaced with the `[workspace.dependencies]` entry.
+#[test]
+fn resolved_dependency_inheritance() {
+    let m = Manifest::from_path_resolved("tests/workspace/member/Cargo.toml")
+        .expect("load workspace member");
+    let deps = m.dependencies.as_ref().unwrap();
+    let serde = deps.get("serde").unwrap().detail().unwrap();
+    assert_eq!(Some("1.0"), serde.version.as_deref());
+    assert_eq!(
+        Some(vec!["derive".to_string(), "rc".to_string()]),
+        serde.features
+    );
+    assert_eq!(Some(true), serde.optional);
+    assert_eq!(None, serde.workspace);
+    let utils = deps.get("utils").unwrap().detail().unwrap();
+    assert_eq!(Some("../utils"), utils.path.as_deref());
+    let log = m.dev_dependencies.as_ref().unwrap().get("log").unwrap();
+    assert_eq!(&lib::Dependency::Simple("0.4".to_string()), log);
+}
+
+#[test]
+fn invalid_dependency_inheritance() {
+    let workspace = Manifest::from_str(
+        r#"
+[workspace]
+[workspace.dependencies]
+serde = "1.0"
+tokio = { version = "1", optional = true }
+"#,
+    )
+    .unwrap();
+    let workspace_deps = workspace.workspace.unwrap().dependencies.unwrap();
+    let workspace_dir = std::path::Path::new("..");
+
+    let mut m = Manifest::from_str(
+        r#"
+[package]
+name = "m"
+version = "1"
+
+[target.'cfg(unix)'.dev-dependencies]
+rand.workspace = true
+"#,
+    )
+    .unwrap();
+    let err = m
+        .inherit_workspace_dependencies(&workspace_deps, workspace_dir)
+        .unwrap_err();
+    assert!(
+        matches!(err, lib::Error::InheritedUnknownValue(ref key) if key == "target.cfg(unix).dev-dependencies.rand"),
+        "{}",
+        err
+    );
+
+    let mut m = Manifest::from_str(
+        r#"
+[package]
+name = "m"
+version = "1"
+
+[dependencies]
+serde = { workspace = true, version = "2" }
+"#,
+    )
+    .unwrap();
+    assert!(matches!(
+        m.inherit_workspace_dependencies(&workspace_deps, workspace_dir),
+        Err(lib::Error::InvalidDependency(_))
+    ));
+
+    let mut m = Manifest::from_str(
+        r#"
+[package]
+name = "m"
+version = "1"
+
+[dependencies]
+tokio.workspace = true
+"#,
+    )
+    .unwrap();
+    assert!(matches!(
+        m.inherit_workspace_dependencies(&workspace_deps, workspace_dir),
+        Err(lib::Error::InvalidDependency(_))
+    ));
+
+    // `default-features = false` is ignored before edition 2024, and an error since
+    let mut m = Manifest::from_str(
+        r#"
+[package]
+name = "m"
+version = "1"
+edition = "2021"
+
+[dependencies]
+serde = { workspace = true, default-features = false }
+"#,
+    )
+    .unwrap();
+    m.inherit_workspace_dependencies(&workspace_deps, workspace_dir)
+        .unwrap();
+    let serde = &m.dependencies.as_ref().unwrap()["serde"];
+    assert_eq!(&lib::Dependency::Simple("1.0".to_string()), serde);
+
+    let mut m = Manifest::from_str(
+        r#"
+[package]
+name = "m"
+version = "1"
+edition = "2024"
+
+[dependencies]
+serde = { workspace = true, default-features = false }
+"#,
+    )
+    .unwrap();
+    assert!(matches!(
+        m.inherit_workspace_dependencies(&workspace_deps, workspace_dir),
+        Err(lib::Error::InvalidDependency(_))
+    ));
+}
+
+/// Glob patterns in `workspace.members` are expanded, and `exclude` is applied to them.
+#[test]
+fn discover_workspace_members() {
+    let m = Manifest::from_path("tests/globbed/Cargo.toml").expect("load workspace");
+    let dir = std::path::Path::new("tests/globbed");
+    let members = m
+        .workspace
+        .unwrap()
+        .discover_members(&lib::Filesystem::new(dir))
+        .expect("discover members");
+    let paths: Vec<_> = members.keys().map(|k| k.as_str()).collect();
+    assert_eq!(
+        vec!["crates/a", "crates/b", "tools/gen", "tools/nested/deep"],
+        paths
+    );
+    let gen = &members["tools/gen"];
+    assert_eq!(
+        MaybeInherited::Local("0.3.0".to_string()),
+        gen.package.as_ref().unwrap().version
+    );
+    assert_eq!(Some("gen"), gen.bin.as_ref().unwrap()[0].name.as_deref());
+}
+
+/// The root package is a member, and `default-members` selects a subset of the members.
+#[test]
+fn default_workspace_members() {
+    let fs = lib::MemoryFilesystem::new()
+        .with_file(
+            "Cargo.toml",
+            r#"
+[package]
+name = "root"
+version = "0.1.0"
+
+[workspace]
+members = ["crates/*"]
+"#,
+        )
+        .with_file("src/main.rs", "fn main() {}")
+        .with_file(
+            "crates/a/Cargo.toml",
+            "[package]\nname = \"a\"\nversion = \"0.1.0\"\n",
+        )
+        .with_file(
+            "crates/b/Cargo.toml",
+            "[package]\nname = \"b\"\nversion = \"0.1.0\"\n",
+        );
+    let root = Manifest::from_abstract_filesystem(&fs).unwrap();
+    let mut workspace = root.workspace.unwrap();
+    let members = workspace.discover_members(&fs).unwrap();
+    assert_eq!(
+        vec![".", "crates/a", "crates/b"],
+        members.keys().collect::<Vec<_>>()
+    );
+    assert_eq!("root", members["."].package.as_ref().unwrap().name);
+    assert_eq!(
+        Some("root"),
+        members["."].bin.as_ref().unwrap()[0].name.as_deref()
+    );
+
+    // Without `default-members`, only the root package is built
+    let defaults = workspace.discover_default_members(&fs).unwrap();
+    assert_eq!(vec!["."], defaults.keys().collect::<Vec<_>>());
+
+    workspace.default_members = Some(vec!["crates/*".into()]);
+    let defaults = workspace.discover_default_members(&fs).unwrap();
+    assert_eq!(
+        vec!["crates/a", "crates/b"],
+        defaults.keys().collect::<Vec<_>>()
+    );
+
+    workspace.default_members = Some(vec!["crates/c".into()]);
+    assert!(matches!(
+        workspace.discover_default_members(&fs),
+        Err(lib::Error::WorkspaceIntegrity(_))
+    ));
+}
+
+/// `**` stops at a fixed depth, so directory cycles (e.g. symlinks to a parent) terminate.
+#[test]
+fn workspace_member_glob_cycle() {
+    struct Cycle;
+    impl lib::AbstractFilesystem for Cycle {
+        fn file_names_in(&self, _: &str) -> std::io::Result<BTreeSet<Box<str>>> {
+            Ok(["loop".into()].into())
+        }
+        fn read_file(&self, rel_path: &str) -> std::io::Result<Vec<u8>> {
+            match rel_path {
+                "Cargo.toml" => Ok(b"[workspace]\nmembers = [\"**\"]\n".to_vec()),
+                _ => Err(std::io::ErrorKind::NotFound.into()),
+            }
+        }
+        fn exists(&self, rel_path: &str) -> bool {
+            rel_path == "Cargo.toml"
+        }
+    }
+    let root = Manifest::from_slice(&Cycle.read_file("Cargo.toml").unwrap()).unwrap();
+    let members = root.workspace.unwrap().discover_members(&Cycle).unwrap();
+    assert_eq!(vec!["."], members.keys().collect::<Vec<_>>());
+}
+
+#[test]
+fn lints() {
+    let m = Manifest::from_str(
+        r#"
+[package]
+name = "foo"
+version = "1"
+
+[lints.rust]
+unsafe_code = "forbid"
+unexpected_cfgs = { level = "warn", check-cfg = ["cfg(fuzzing)"] }
+
+[lints.clippy]
+pedantic = { level = "warn", priority = -1 }
+
+[lints.cranky]
+fallible_impl_from = "deny"
+"#,
+    )
+    .unwrap();
+    let lints = m.lints.as_ref().unwrap().as_local().unwrap();
+    let rust = lints.rust.as_ref().unwrap();
+    assert_eq!(lib::LintLevel::Forbid, rust["unsafe_code"].level());
+    let unexpected_cfgs = match &rust["unexpected_cfgs"] {
+        lib::Lint::Detailed(detail) => detail,
+        lint => panic!("expected a detailed lint, got {:?}", lint),
+    };
+    assert_eq!(
+        Some(vec!["cfg(fuzzing)".to_string()]),
+        unexpected_cfgs.check_cfg
+    );
+    let pedantic = &lints.clippy.as_ref().unwrap()["pedantic"];
+    assert_eq!(lib::LintLevel::Warn, pedantic.level());
+    assert_eq!(-1, pedantic.priority());
+    assert_eq!(
+        lib::LintLevel::Deny,
+        lints.tools["cranky"]["fallible_impl_from"].level()
+    );
+
+    let round_trip = Manifest::from_str(&toml::to_string(&m).unwrap()).unwrap();
+    assert_eq!(m.lints, round_trip.lints);
+}
+
+#[test]
+fn workspace_lints() {
+    let workspace = Manifest::from_str(
+        r#"
+[workspace]
+[workspace.lints.rust]
+unsafe_code = "deny"
+"#,
+    )
+    .unwrap()
+    .workspace
+    .unwrap();
+    let mut m = Manifest::from_str(
+        r#"
+[package]
+name = "foo"
+version = "1"
+
+[lints]
+workspace = true
+"#,
+    )
+    .unwrap();
+    assert_eq!(Some(MaybeInherited::inherited()), m.lints);
+    m.inherit_workspace(&workspace, std::path::Path::new(".."))
+        .unwrap();
+    assert_eq!(workspace.lints.map(MaybeInherited::Local), m.lints);
+}
+
+#[test]
+fn validate() {
+    let m = Manifest::from_str(
+        r#"
+[package]
+name = "foo"
+version = "1"
+default-run = "missing"
+keywords = ["a", "b", "c", "d", "e", "f"]
+
+[features]
+std = []
+
+[dependencies]
+serde = { version = "1", optional = true }
+
+[lib]
+crate-type = ["rlib", "shared"]
+
+[[bin]]
+name = "foo"
+required-features = ["std", "serde", "serde/derive", "nope"]
+
+[[bin]]
+name = "foo"
+"#,
+    )
+    .unwrap();
+    let diagnostics = m.validate();
+    let found: Vec<_> = diagnostics
+        .iter()
+        .map(|d| (d.severity, d.code, d.key.as_str()))
+        .collect();
+    use lib::Severity::{Error, Warning};
+    assert_eq!(
+        vec![
+            (Warning, "too-many-keywords", "package.keywords"),
+            (Error, "unknown-default-run", "package.default-run"),
+            (Error, "invalid-crate-type", "lib.crate-type"),
+            (Error, "unknown-required-feature", "bin.0.required-features"),
+            (Error, "duplicate-product-name", "bin.1.name"),
+        ],
+        found
+    );
+
+    let m = Manifest::from_path("tests/autobin/Cargo.toml").unwrap();
+    assert_eq!(Vec::<lib::Diagnostic>::new(), m.validate());
+}
+
+#[test]
+fn feature_graph() {
+    let m = Manifest::from_str(
+        r#"
+[package]
+name = "foo"
+version = "1"
+
+[features]
+default = ["std"]
+std = ["serde?/std", "log/std"]
+derive = ["dep:serde", "serde/derive"]
+tracing = ["tokio/tracing"]
+
+[dependencies]
+serde = { version = "1", optional = true }
+log = "0.4"
+tokio = { version = "1", optional = true }
+"#,
+    )
+    .unwrap();
+    let graph = m.feature_graph().unwrap();
+    // `tokio` is never referenced with `dep:`, so it gets an implicit feature
+    assert!(graph.features().contains_key("tokio"));
+    assert!(!graph.features().contains_key("serde"));
+
+    let active = graph.resolve::<&str>(&[], true).unwrap();
+    assert_eq!(
+        vec!["default", "std"],
+        active.features.iter().collect::<Vec<_>>()
+    );
+    assert!(active.dependencies.is_empty());
+    assert!(!active.dependency_features.contains_key("serde"));
+    assert!(active.dependency_features["log"].contains("std"));
+
+    let active = graph.resolve(&["derive", "tracing"], true).unwrap();
+    assert_eq!(
+        vec!["serde", "tokio"],
+        active.dependencies.iter().collect::<Vec<_>>()
+    );
+    assert!(active.features.contains("tokio"));
+    assert_eq!(
+        vec!["derive", "std"],
+        active.dependency_features["serde"]
+            .iter()
+            .collect::<Vec<_>>()
+    );
+
+    assert!(matches!(
+        graph.resolve(&["nope"], false),
+        Err(lib::Error::InvalidFeature(_))
+    ));
+}
+
+#[test]
+fn feature_cycle() {
+    let m = Manifest::from_str(
+        r#"
+[package]
+name = "foo"
+version = "1"
+
+[features]
+a = ["b"]
+b = ["c"]
+c = ["a"]
+"#,
+    )
+    .unwrap();
+    match m.feature_graph() {
+        Err(lib::Error::FeatureCycle(cycle)) => assert_eq!(vec!["a", "b", "c", "a"], cycle),
+        other => panic!("expected a cycle, got {:?}", other),
+    }
+}
+
+#[test]
+fn feature_of_unknown_dependency() {
+    let toml = r#"
+[package]
+name = "foo"
+version = "1"
+
+[features]
+std = ["serde/std"]
+
+[dependencies]
+log = "0.4"
+
+[dev-dependencies]
+tokio = "1"
+"#;
+    let m = Manifest::from_str(toml).unwrap();
+    match m.feature_graph() {
+        Err(lib::Error::InvalidFeature(msg)) => assert!(msg.contains("`serde/std`"), "{}", msg),
+        other => panic!("expected an invalid feature, got {:?}", other),
+    }
+    let m = Manifest::from_str(&toml.replace("serde/std", "serde?/std")).unwrap();
+    assert!(matches!(
+        m.feature_graph(),
+        Err(lib::Error::InvalidFeature(_))
+    ));
+    // Features can't enable features of dev-dependencies
+    let m = Manifest::from_str(&toml.replace("serde/std", "tokio/full")).unwrap();
+    assert!(matches!(
+        m.feature_graph(),
+        Err(lib::Error::InvalidFeature(_))
+    ));
+    let m = Manifest::from_str(&toml.replace("serde/std", "log/std")).unwrap();
+    assert!(m.feature_graph().is_ok());
+}
+
+#[test]
+fn platform() {
+    use lib::{Cfg, CfgExpr, Platform};
+
+    let platform: Platform = r#"cfg(all(unix, not(target_os = "macos"), any(target_arch="x86_64", target_arch = "aarch64",)))"#
+        .parse()
+        .unwrap();
+    assert_eq!(
+        r#"cfg(all(unix, not(target_os = "macos"), any(target_arch = "x86_64", target_arch = "aarch64")))"#,
+        platform.to_string()
+    );
+    let linux = [
+        Cfg::Name("unix".into()),
+        Cfg::KeyPair("target_os".into(), "linux".into()),
+        Cfg::KeyPair("target_arch".into(), "x86_64".into()),
+    ];
+    let macos = [
+        Cfg::Name("unix".into()),
+        Cfg::KeyPair("target_os".into(), "macos".into()),
+        Cfg::KeyPair("target_arch".into(), "aarch64".into()),
+    ];
+    assert!(platform.matches("x86_64-unknown-linux-gnu", &linux));
+    assert!(!platform.matches("aarch64-apple-darwin", &macos));
+
+    let triple: Platform = "x86_64-pc-windows-msvc".parse().unwrap();
+    assert_eq!(Platform::Name("x86_64-pc-windows-msvc".into()), triple);
+    assert!(triple.matches("x86_64-pc-windows-msvc", &[]));
+
+    assert!("cfg(all(unix)".parse::<Platform>().is_err());
+    assert!("cfg(not(unix, windows))".parse::<Platform>().is_err());
+    assert!("cfg(target_os = linux)".parse::<Platform>().is_err());
+    assert_eq!(
+        CfgExpr::Value(Cfg::Name("windows".into())),
+        "windows".parse().unwrap()
+    );
+}
+
+#[test]
+fn dependencies_for_platform() {
+    let m = Manifest::from_str(
+        r#"
+[package]
+name = "foo"
+version = "1"
+
+[dependencies]
+log = "0.4"
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+
+[target.'cfg(windows)'.dependencies]
+winapi = "0.3"
+
+[target.x86_64-unknown-linux-gnu.dependencies]
+log = { version = "0.4", features = ["std"] }
+
+[target.'cfg(unix'.dependencies]
+broken = "1"
+"#,
+    )
+    .unwrap();
+    let deps =
+        m.dependencies_for_target("x86_64-unknown-linux-gnu", &[lib::Cfg::Name("unix".into())]);
+    assert_eq!(vec!["libc", "log"], deps.keys().collect::<Vec<_>>());
+    assert_eq!(&["std".to_string()], deps["log"].req_features());
+
+    let windows = lib::Platform::from_str("cfg( windows )").unwrap();
+    let deps = m.dependencies_for_platform(&windows);
+    assert_eq!(vec!["log", "winapi"], deps.keys().collect::<Vec<_>>());
+    assert!(deps["log"].req_features().is_empty());
+
+    let invalid: Vec<_> = m
+        .validate()
+        .into_iter()
+        .filter(|d| d.code == "invalid-target")
+        .map(|d| d.key)
+        .collect();
+    assert_eq!(vec!["target.cfg(unix"], invalid);
+}
+
+#[test]
+fn editions() {
+    let m = Manifest::from_str("[package]\nname = \"foo\"\nversion = \"1\"\nedition = \"2024\"")
+        .unwrap();
+    assert_eq!(
+        Some(MaybeInherited::Local(lib::Edition::E2024)),
+        m.package.unwrap().edition
+    );
+
+    let m = Manifest::from_str("[package]\nname = \"foo\"\nversion = \"1\"\nedition = \"2027\"")
+        .unwrap();
+    let future = match m.package.as_ref().unwrap().edition {
+        Some(MaybeInherited::Local(edition)) => edition,
+        ref other => panic!("unexpected edition {:?}", other),
+    };
+    assert_eq!(lib::Edition::Unknown(2027), future);
+    assert_eq!("2027", future.to_string());
+    assert!(toml::to_string(&m).unwrap().contains("edition = \"2027\""));
+    let diagnostics = m.validate();
+    assert_eq!(1, diagnostics.len());
+    assert_eq!("unknown-edition", diagnostics[0].code);
+    assert_eq!("package.edition", diagnostics[0].key);
+
+    // Editions are ordered by year
+    assert!(lib::Edition::E2015 < lib::Edition::E2018);
+    assert!(lib::Edition::E2021 < lib::Edition::E2024);
+    assert!(lib::Edition::E2024 < future);
+    assert!(lib::Edition::Unknown(2010) < lib::Edition::E2015);
+
+    assert!(
+        Manifest::from_str("[package]\nname = \"foo\"\nversion = \"1\"\nedition = \"foo\"")
+            .is_err()
+    );
+    assert!(lib::Edition::from_str("20211").is_err());
+    // No edition predates 2015, so leading zeros are rejected instead of changing the spelling
+    assert!(lib::Edition::from_str("0042").is_err());
+    assert!(lib::Edition::from_str("1999").is_err());
+}
+
+#[test]
+fn effective_resolver() {
+    let m = Manifest::from_str("[package]\nname = \"foo\"\nversion = \"1\"").unwrap();
+    assert_eq!(lib::Resolver::V1, m.effective_resolver(None));
+
+    let m = Manifest::from_str("[package]\nname = \"foo\"\nversion = \"1\"\nedition = \"2021\"")
+        .unwrap();
+    assert_eq!(lib::Resolver::V2, m.effective_resolver(None));
+
+    let m = Manifest::from_str("[package]\nname = \"foo\"\nversion = \"1\"\nedition = \"2024\"")
+        .unwrap();
+    assert_eq!(lib::Resolver::V3, m.effective_resolver(None));
+
+    let m = Manifest::from_str(
+        r#"
+[package]
+name = "foo"
+version = "1"
+edition = "2024"
+resolver = "1"
+"#,
+    )
+    .unwrap();
+    assert_eq!(lib::Resolver::V1, m.effective_resolver(None));
+
+    let m = Manifest::from_str(
+        r#"
+[package]
+name = "foo"
+version = "1"
+edition = "2024"
+
+[workspace]
+resolver = "2"
+"#,
+    )
+    .unwrap();
+    assert_eq!(lib::Resolver::V2, m.effective_resolver(None));
+
+    let m = Manifest::from_str(
+        r#"
+[package]
+name = "foo"
+version = "1"
+edition.workspace = true
+
+[workspace.package]
+edition = "2024"
+"#,
+    )
+    .unwrap();
+    assert_eq!(lib::Resolver::V3, m.effective_resolver(None));
+
+    let root = Manifest::from_str("[workspace]\nresolver = \"3\"").unwrap();
+    assert_eq!(lib::Resolver::V3, root.effective_resolver(None));
+    let m = Manifest::from_str("[workspace]\nmembers = []").unwrap();
+    assert_eq!(lib::Resolver::V1, m.effective_resolver(None));
+
+    // The root's resolver applies to members, whatever they set themselves
+    let member = Manifest::from_str(
+        r#"
+[package]
+name = "member"
+version = "1"
+edition = "2021"
+resolver = "1"
+"#,
+    )
+    .unwrap();
+    assert_eq!(lib::Resolver::V1, member.effective_resolver(None));
+    assert_eq!(lib::Resolver::V3, member.effective_resolver(Some(&root)));
+}
+
+#[test]
+fn typed_profiles() {
+    let toml = r#"[package]
+name = "foo"
+version = "1"
+
+[profile.dev]
+opt-level = "s"
+debug = 1
+lto = false
+panic = "abort"
+strip = true
+split-debuginfo = "unpacked"
+trim-paths = ["macro", "diagnostics"]
+
+[profile.dev.build-override]
+opt-level = "3"
+debug = "line-tables-only"
+
+[profile.release]
+opt-level = "z"
+debug = true
+lto = "thin"
+strip = "debuginfo"
+trim-paths = "all"
+"#;
+    let profiles = Manifest::from_str(toml).unwrap().profile.unwrap();
+    let dev = profiles.dev.as_ref().unwrap();
+    assert_eq!(Some(lib::OptLevel::S), dev.opt_level);
+    assert_eq!(Some(lib::DebugInfo::Level(1)), dev.debug);
+    assert_eq!(lib::DebugInfo::Limited, dev.debug.unwrap().normalized());
+    assert_eq!(Some(lib::Lto::Bool(false)), dev.lto);
+    assert_eq!(Some(lib::PanicStrategy::Abort), dev.panic);
+    assert_eq!(lib::Strip::Symbols, dev.strip.clone().unwrap().normalized());
+    assert_eq!(Some(lib::SplitDebuginfo::Unpacked), dev.split_debuginfo);
+    assert_eq!(
+        Some(lib::TrimPaths::Scopes(vec![
+            lib::TrimPathsScope::Macro,
+            lib::TrimPathsScope::Diagnostics
+        ])),
+        dev.trim_paths
+    );
+    let build_override = dev.build_override.as_ref().unwrap();
+    assert_eq!(Some(lib::OptLevel::Quoted(3)), build_override.opt_level);
+    assert_eq!(
+        lib::OptLevel::O3,
+        build_override.opt_level.unwrap().normalized()
+    );
+    assert_eq!(Some(lib::DebugInfo::LineTablesOnly), build_override.debug);
+    let release = profiles.release.as_ref().unwrap();
+    assert_eq!(Some(lib::DebugInfo::Bool(true)), release.debug);
+    assert_eq!(Some(lib::Lto::Thin), release.lto);
+    assert_eq!(Some(lib::Strip::Debuginfo), release.strip);
+
+    // Settings keep their spelling when serialized again
+    let round_trip: toml::Value = toml::Value::try_from(&profiles).unwrap();
+    let original: toml::Value = toml::from_str::<toml::Value>(toml).unwrap()["profile"].clone();
+    assert_eq!(original["dev"]["debug"], round_trip["dev"]["debug"]);
+    assert_eq!(original["dev"]["lto"], round_trip["dev"]["lto"]);
+    assert_eq!(original["dev"]["strip"], round_trip["dev"]["strip"]);
+    assert_eq!(original["release"]["debug"], round_trip["release"]["debug"]);
+    assert_eq!(
+        original["release"]["opt-level"],
+        round_trip["release"]["opt-level"]
+    );
+    assert_eq!(
+        original["dev"]["build-override"]["opt-level"],
+        round_trip["dev"]["build-override"]["opt-level"]
+    );
+
+    let package = "[package]\nname = \"foo\"\nversion = \"1\"\n";
+    assert!(Manifest::from_str(&format!("{}[profile.dev]\nopt-level = 2", package)).is_ok());
+    assert!(Manifest::from_str(&format!("{}[profile.dev]\nopt-level = 4", package)).is_err());
+    assert!(Manifest::from_str(&format!("{}[profile.dev]\ndebug = \"some\"", package)).is_err());
+
+    // Cargo accepts other spellings of lto and strip, and passes unknown ones to rustc
+    let other = format!(
+        "{}[profile.dev]\nlto = \"no\"\nstrip = \"off\"\n[profile.release]\nlto = \"yes\"",
+        package
+    );
+    let manifest = Manifest::from_str(&other).unwrap();
+    let profiles = manifest.profile.as_ref().unwrap();
+    let dev = profiles.dev.as_ref().unwrap();
+    assert_eq!(Some(lib::Lto::Named("no".into())), dev.lto);
+    assert_eq!(lib::Lto::Off, dev.lto.clone().unwrap().normalized());
+    assert_eq!(Some(lib::Strip::Named("off".into())), dev.strip);
+    assert_eq!(lib::Strip::None, dev.strip.clone().unwrap().normalized());
+    let release = profiles.release.as_ref().unwrap();
+    assert_eq!(Some(lib::Lto::Named("yes".into())), release.lto);
+    let round_trip: toml::Value = toml::Value::try_from(profiles).unwrap();
+    assert_eq!(toml::Value::from("no"), round_trip["dev"]["lto"]);
+    assert_eq!(toml::Value::from("off"), round_trip["dev"]["strip"]);
+    let resolved = profiles.resolve("dev").unwrap();
+    assert_eq!(lib::Lto::Off, resolved.lto);
+    assert_eq!(lib::Strip::None, resolved.strip);
+}
+
+#[test]
+fn resolved_profiles() {
+    let toml = r#"[package]
+name = "foo"
+version = "1"
+
+[profile.dev]
+opt-level = 1
+
+[profile.dev.package."*"]
+opt-level = 2
+
+[profile.release]
+lto = true
+
+[profile.release.build-override]
+opt-level = 1
+
+[profile.bench]
+debug = 2
+
+[profile.dist]
+inherits = "release"
+strip = true
+codegen-units = 1
+
+[profile.dist.package.foo]
+debug = true
+
+[profile.dist.build-override]
+codegen-units = 4
+
+[profile.loop-a]
+inherits = "loop-b"
+
+[profile.loop-b]
+inherits = "loop-a"
+
+[profile.orphan]
+inherits = "missing"
+
+[profile.unrooted]
+opt-level = 2
+"#;
+    let profiles = Manifest::from_str(toml).unwrap().profile.unwrap();
+
+    let dev = profiles.resolve("dev").unwrap();
+    assert_eq!(lib::OptLevel::O1, dev.opt_level);
+    assert_eq!(lib::DebugInfo::Full, dev.debug);
+    assert!(dev.debug_assertions && dev.incremental);
+    assert_eq!(256, dev.codegen_units);
+    assert_eq!(Some(lib::OptLevel::O2), dev.package["*"].opt_level);
+
+    // `test` is built in and inherits from `dev`
+    let test = profiles.resolve("test").unwrap();
+    assert_eq!("test", test.name);
+    assert_eq!(lib::OptLevel::O1, test.opt_level);
+
+    let bench = profiles.resolve("bench").unwrap();
+    assert_eq!(lib::OptLevel::O3, bench.opt_level);
+    assert_eq!(lib::DebugInfo::Full, bench.debug);
+    assert_eq!(lib::Lto::Bool(true), bench.lto);
+
+    let dist = profiles.resolve("dist").unwrap();
+    assert_eq!(lib::OptLevel::O3, dist.opt_level);
+    assert_eq!(lib::DebugInfo::None, dist.debug);
+    assert!(!dist.debug_assertions && !dist.incremental);
+    assert_eq!(lib::Lto::Bool(true), dist.lto);
+    assert_eq!(lib::Strip::Symbols, dist.strip);
+    assert_eq!(1, dist.codegen_units);
+    assert!(dist.package.contains_key("foo"));
+    // Built-in build-override defaults, then the `build-override` tables along the chain
+    let build_override = dist.build_override.unwrap();
+    assert_eq!(lib::OptLevel::O1, build_override.opt_level);
+    assert_eq!(lib::DebugInfo::None, build_override.debug);
+    assert_eq!(4, build_override.codegen_units);
+    assert_eq!(lib::Lto::Bool(true), build_override.lto);
+
+    let err = profiles.resolve("loop-a").unwrap_err().to_string();
+    assert_eq!("profile inheritance cycle: loop-a -> loop-b -> loop-a", err);
+    let err = profiles.resolve("orphan").unwrap_err().to_string();
+    assert_eq!("profile `missing` is not defined", err);
+    assert!(profiles.resolve("unrooted").is_err());
+    assert!(profiles.resolve("nonexistent").is_err());
+
+    // Only custom profiles can inherit
+    let mut root = profiles.clone();
+    root.dev.as_mut().unwrap().inherits = Some("release".into());
+    let err = root.resolve("test").unwrap_err();
+    assert!(matches!(err, lib::Error::InvalidProfile(_)));
+    assert_eq!(
+        "`inherits` must not be specified in root profile `dev`",
+        err.to_string()
+    );
+}
+
+#[test]
+fn profile_package_overrides() {
+    let toml = r#"[package]
+name = "foo"
+version = "1"
+
+[profile.dev.package."*"]
+opt-level = 2
+debug = false
+
+[profile.dev.package.regex]
+opt-level = 3
+
+[profile.dev.package."image@0.23"]
+opt-level = "s"
+codegen-units = 1
+
+[profile.dev.package."image@0.24.1"]
+debug-assertions = false
+"#;
+    let profiles = Manifest::from_str(toml).unwrap().profile.unwrap();
+    let overrides = &profiles.dev.as_ref().unwrap().package;
+    assert_eq!(Some(lib::OptLevel::O3), overrides["regex"].opt_level);
+    assert_eq!(Some(1), overrides["image@0.23"].codegen_units);
+
+    let dev = profiles.resolve("dev").unwrap();
+    let serde = dev.settings_for("serde", "1.0.0").unwrap();
+    assert_eq!(lib::OptLevel::O2, serde.opt_level);
+    assert_eq!(lib::DebugInfo::None, serde.debug);
+
+    let regex = dev.settings_for("regex", "1.10.0").unwrap();
+    assert_eq!(lib::OptLevel::O3, regex.opt_level);
+    assert_eq!(lib::DebugInfo::None, regex.debug);
+
+    let image = dev.settings_for("image", "0.24.1").unwrap();
+    assert_eq!(lib::OptLevel::O2, image.opt_level);
+    assert!(!image.debug_assertions);
+
+    let old_image = dev.settings_for("image", "0.23.14").unwrap();
+    assert_eq!(lib::OptLevel::S, old_image.opt_level);
+    assert_eq!(1, old_image.codegen_units);
+    assert!(old_image.debug_assertions);
+
+    // Like Cargo, a package can't match more than one spec besides `*`
+    let mut ambiguous = profiles.dev.clone().unwrap();
+    ambiguous
+        .package
+        .insert("image".into(), overrides["regex"].clone());
+    let ambiguous = lib::Profiles {
+        dev: Some(ambiguous),
+        ..Default::default()
+    };
+    let err = ambiguous
+        .resolve("dev")
+        .unwrap()
+        .settings_for("image", "0.23.14")
+        .unwrap_err();
+    assert_eq!(
+        "multiple package overrides in profile `dev` match package `image v0.23.14`: image, image@0.23",
+        err.to_string()
+    );
+    assert!(ambiguous
+        .resolve("dev")
+        .unwrap()
+        .settings_for("serde", "1.0.0")
+        .is_ok());
+
+    // The profile itself is unchanged
+    assert_eq!(lib::OptLevel::O0, dev.opt_level);
+    assert!(Manifest::from_str(&format!(
+        "{}[profile.dev.package.foo]\nopt-level = 9",
+        "[package]\nname = \"foo\"\nversion = \"1\"\n"
+    ))
+    .is_err());
+
+    // Keys that aren't package specs are errors, not overrides that never match
+    let mut invalid = profiles.dev.clone().unwrap();
+    invalid
+        .package
+        .insert("image@not-a-version".into(), overrides["regex"].clone());
+    let invalid = lib::Profiles {
+        dev: Some(invalid),
+        ..Default::default()
+    };
+    assert!(matches!(
+        invalid.resolve("test"),
+        Err(lib::Error::InvalidProfile(msg)) if msg.contains("`profile.dev.package`")
+    ));
+    let mut resolved = dev.clone();
+    resolved
+        .package
+        .insert("image@not-a-version".into(), overrides["regex"].clone());
+    assert!(matches!(
+        resolved.settings_for("serde", "1.0.0"),
+        Err(lib::Error::InvalidProfile(_))
+    ));
+}
+
+#[test]
+fn artifact_dependencies() {
+    let toml = r#"[package]
+name = "foo"
+version = "1"
+
+[dependencies]
+tool = { path = "tool", artifact = "bin" }
+plugins = { path = "plugins", artifact = ["bin:gen", "cdylib", "staticlib"], lib = true, target = "wasm32-unknown-unknown" }
+same = { path = "same", artifact = "bin", target = "target" }
+plain = { version = "1", lib = true }
+
+[target.'cfg(unix)'.build-dependencies]
+other = { version = "1", target = "target" }
+"#;
+    let m = Manifest::from_str(toml).unwrap();
+    let deps = m.dependencies.as_ref().unwrap();
+    let tool = deps["tool"].detail().unwrap();
+    assert_eq!(
+        Some(lib::Artifact::Single(lib::ArtifactKind::Bin)),
+        tool.artifact
+    );
+    let plugins = deps["plugins"].detail().unwrap();
+    assert_eq!(
+        &[
+            lib::ArtifactKind::BinName("gen".into()),
+            lib::ArtifactKind::Cdylib,
+            lib::ArtifactKind::Staticlib
+        ],
+        plugins.artifact.as_ref().unwrap().kinds()
+    );
+    assert_eq!(Some(true), plugins.lib);
+    assert_eq!(
+        Some(lib::ArtifactTarget::Triple("wasm32-unknown-unknown".into())),
+        plugins.target
+    );
+    let same = deps["same"].detail().unwrap();
+    assert_eq!(Some(lib::ArtifactTarget::Target), same.target);
+
+    // Round trip keeps the fields
+    let round_trip = Manifest::from_str(&toml::to_string(&m).unwrap()).unwrap();
+    assert_eq!(deps, round_trip.dependencies.as_ref().unwrap());
+
+    let keys: Vec<(lib::Severity, String)> = m
+        .validate()
+        .into_iter()
+        .filter(|d| d.code == "artifact-key-without-artifact")
+        .map(|d| (d.severity, d.key))
+        .collect();
+    assert_eq!(
+        vec![
+            (lib::Severity::Error, "dependencies.plain.lib".to_string()),
+            (
+                lib::Severity::Error,
+                "target.cfg(unix).build-dependencies.other.target".to_string()
+            )
+        ],
+        keys
+    );
+
+    let bad = toml.replace("artifact = \"bin\" }", "artifact = \"dylib\" }");
+    assert!(Manifest::from_str(&bad).is_err());
+}
+
+#[test]
+fn dependency_source() {
+    use lib::{DependencySource, GitReference};
+
+    let toml = r#"[package]
+name = "foo"
+version = "1"
+
+[dependencies]
+simple = "1"
+explicit = { version = "1", registry = "crates-io" }
+private = { version = "1", registry = "my-registry" }
+indexed = { version = "1", registry-index = "https://example.com/index" }
+local = { path = "../local", version = "1", registry = "my-registry" }
+git = { git = "https://github.com/a/b" }
+tagged = { git = "https://github.com/a/b", tag = "v1" }
+ambiguous = { git = "https://github.com/a/b", path = "b" }
+two-refs = { git = "https://github.com/a/b", branch = "main", rev = "abc" }
+no-git = { version = "1", branch = "main" }
+two-registries = { registry = "a", registry-index = "https://example.com/index" }
+inherited = { workspace = true }
+"#;
+    let m = Manifest::from_str(toml).unwrap();
+    let deps = m.dependencies.as_ref().unwrap();
+    let source = |name: &str| deps[name].source();
+
+    assert_eq!(DependencySource::CratesIo, source("simple").unwrap());
+    assert_eq!(DependencySource::CratesIo, source("explicit").unwrap());
+    assert!(deps["simple"].is_crates_io());
+    assert_eq!(
+        DependencySource::Registry {
+            name: "my-registry".into()
+        },
+        source("private").unwrap()
+    );
+    assert!(!deps["private"].is_crates_io());
+    assert_eq!(
+        DependencySource::RegistryIndex {
+            url: "https://example.com/index".into()
+        },
+        source("indexed").unwrap()
+    );
+    assert_eq!(
+        DependencySource::Path {
+            path: "../local".into()
+        },
+        source("local").unwrap()
+    );
+    assert_eq!(
+        DependencySource::Git {
+            url: "https://github.com/a/b".into(),
+            reference: GitReference::DefaultBranch
+        },
+        source("git").unwrap()
+    );
+    assert_eq!(
+        DependencySource::Git {
+            url: "https://github.com/a/b".into(),
+            reference: GitReference::Tag("v1".into())
+        },
+        source("tagged").unwrap()
+    );
+
+    let err = source("ambiguous").unwrap_err().to_string();
+    assert!(err.contains("`git` and `path`"), "{}", err);
+    let err = source("two-refs").unwrap_err().to_string();
+    assert!(err.contains("`branch` and `rev`"), "{}", err);
+    let err = source("no-git").unwrap_err().to_string();
+    assert!(err.contains("requires `git`"), "{}", err);
+    assert!(source("two-registries").is_err());
+    assert!(source("inherited").is_err());
+    // `is_crates_io` only looks for keys naming another source
+    assert!(deps["inherited"].is_crates_io());
+}
+
+#[cfg(feature = "semver")]
+#[test]
+fn semver() {
+    let toml = r#"[package]
+name = "foo"
+version = "1.2.3-beta.1"
+
+[dependencies]
+serde = "1.0.100"
+git = { git = "https://github.com/a/b" }
+broken = "not a version"
+inherited = { workspace = true }
+"#;
+    let m = Manifest::from_str(toml).unwrap();
+    let version = m.package.as_ref().unwrap().semver_version().unwrap();
+    assert_eq!(semver::Version::parse("1.2.3-beta.1").unwrap(), version);
+
+    let deps = m.dependencies.as_ref().unwrap();
+    let req = deps["serde"].semver_req().unwrap();
+    assert!(req.matches(&semver::Version::new(1, 0, 200)));
+    assert!(!req.matches(&semver::Version::new(1, 0, 99)));
+    assert_eq!(semver::VersionReq::STAR, deps["git"].semver_req().unwrap());
+
+    let err = deps["broken"].semver_req().unwrap_err().to_string();
+    assert!(err.contains("`not a version`"), "{}", err);
+    let broken = m.all_dependencies().find(|d| d.name == "broken").unwrap();
+    let err = broken.semver_req().unwrap_err().to_string();
+    assert!(err.contains("`dependencies.broken`"), "{}", err);
+    let inherited = m
+        .all_dependencies()
+        .find(|d| d.name == "inherited")
+        .unwrap();
+    let err = inherited.semver_req().unwrap_err().to_string();
+    assert!(err.contains("`dependencies.inherited`"), "{}", err);
+    assert!(deps["inherited"].semver_req().is_err());
+
+    let inherited = toml.replace("version = \"1.2.3-beta.1\"", "version.workspace = true");
+    let m = Manifest::from_str(&inherited).unwrap();
+    assert!(m.package.as_ref().unwrap().semver_version().is_err());
+}
+
+#[test]
+fn all_dependencies() {
+    use lib::DepKind;
+
+    let toml = r#"[package]
+name = "foo"
+version = "1"
+
+[dependencies]
+serde = "1"
+json = { package = "serde_json", version = "1" }
+
+[dev-dependencies]
+criterion = "0.5"
+
+[build-dependencies]
+cc = "1"
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+
+[target.wasm32-unknown-unknown.dev-dependencies]
+wasm-bindgen-test = "0.3"
+"#;
+    let mut m = Manifest::from_str(toml).unwrap();
+    let all: Vec<_> = m
+        .all_dependencies()
+        .map(|d| (d.name, d.package_name, d.kind, d.platform))
+        .collect();
+    assert_eq!(
+        vec![
+            ("json", "serde_json", DepKind::Normal, None),
+            ("serde", "serde", DepKind::Normal, None),
+            ("criterion", "criterion", DepKind::Dev, None),
+            ("cc", "cc", DepKind::Build, None),
+            ("libc", "libc", DepKind::Normal, Some("cfg(unix)")),
+            (
+                "wasm-bindgen-test",
+                "wasm-bindgen-test",
+                DepKind::Dev,
+                Some("wasm32-unknown-unknown")
+            ),
+        ],
+        all
+    );
+    let keys: Vec<String> = m.all_dependencies().map(|d| d.key()).collect();
+    assert_eq!("target.cfg(unix).dependencies.libc", keys[4]);
+
+    let package_names: Vec<String> = m
+        .all_dependencies_mut()
+        .map(|d| d.package_name().to_string())
+        .collect();
+    assert_eq!(["serde_json", "serde"], package_names[..2]);
+
+    for entry in m.all_dependencies_mut() {
+        if entry.platform.is_some() {
+            *entry.dep = lib::Dependency::Simple("*".into());
+        }
+    }
+    let target = m.target.as_ref().unwrap();
+    assert_eq!("*", target["cfg(unix)"].dependencies["libc"].req());
+    assert_eq!("1", m.dependencies.as_ref().unwrap()["serde"].req());
+}
+
+#[test]
+fn add_remove_dependency() {
+    use lib::{DepKind, Dependency, DependencyDetail};
+
+    let toml = r#"[package]
+name = "foo"
+version = "1"
+
+[features]
+default = ["json", "dep:yaml"]
+json = ["dep:serde_json", "serde/derive"]
+color = ["ansi?/std", "ansi"]
+
+[dependencies]
+serde = "1"
+serde_json = { version = "1", optional = true }
+yaml = { version = "0.9", optional = true }
+ansi = { version = "0.1", optional = true }
+
+[build-dependencies]
+"#;
+    let mut m = Manifest::from_str(toml).unwrap();
+    let features = |names: &[&str]| DependencyDetail {
+        features: Some(names.iter().map(|f| f.to_string()).collect()),
+        ..DependencyDetail::default()
+    };
+
+    // Adding features upgrades a simple dependency, and merges with existing features
+    m.add_dependency(
+        DepKind::Normal,
+        None,
+        "serde",
+        &Dependency::Detailed(features(&["derive"])),
+    );
+    m.add_dependency(
+        DepKind::Normal,
+        None,
+        "serde",
+        &Dependency::Detailed(features(&["derive", "rc"])),
+    );
+    let serde = m.dependencies.as_ref().unwrap()["serde"].clone();
+    assert_eq!("1", serde.req());
+    assert_eq!(
+        &["derive".to_string(), "rc".to_string()],
+        serde.req_features()
+    );
+
+    // A new version replaces the old one, and a plain version stays simple
+    m.add_dependency(
+        DepKind::Dev,
+        None,
+        "tempfile",
+        &Dependency::Simple("3".into()),
+    );
+    m.add_dependency(
+        DepKind::Dev,
+        None,
+        "tempfile",
+        &Dependency::Simple("3.8".into()),
+    );
+    assert_eq!(
+        Dependency::Simple("3.8".into()),
+        m.dev_dependencies.as_ref().unwrap()["tempfile"]
+    );
+
+    // Target tables are created as needed
+    m.add_dependency(
+        DepKind::Normal,
+        Some("cfg(unix)"),
+        "libc",
+        &Dependency::Simple("0.2".into()),
+    );
+    assert!(m.target.as_ref().unwrap()["cfg(unix)"].dependencies["libc"].is_crates_io());
+    assert!(m
+        .remove_dependency(DepKind::Normal, Some("cfg(unix)"), "libc")
+        .is_some());
+    assert!(m.target.is_none());
+
+    // Removing optional dependencies cleans up features
+    assert!(m
+        .remove_dependency(DepKind::Normal, None, "serde_json")
+        .is_some());
+    assert!(m.remove_dependency(DepKind::Normal, None, "yaml").is_some());
+    assert!(m.remove_dependency(DepKind::Normal, None, "ansi").is_some());
+    let features = m.features.as_ref().unwrap();
+    assert_eq!(vec!["json".to_string()], features["default"]);
+    assert_eq!(vec!["serde/derive".to_string()], features["json"]);
+    assert!(features["color"].is_empty());
+    assert!(m.feature_graph().is_ok());
+
+    // Only the table that was removed from is pruned, and missing tables aren't created
+    assert!(m
+        .remove_dependency(DepKind::Normal, Some("cfg(windows)"), "missing")
+        .is_none());
+    assert!(m.target.is_none());
+    assert!(m
+        .remove_dependency(DepKind::Build, None, "missing")
+        .is_none());
+    assert!(m
+        .remove_dependency(DepKind::Dev, None, "tempfile")
+        .is_some());
+    assert!(m.dev_dependencies.is_none());
+    assert_eq!(Some(lib::DepsSet::new()), m.build_dependencies);
+}
+
+#[test]
+fn replace() {
+    let toml = r#"[package]
+name = "foo"
+version = "1"
+
+[replace]
+"serde:1.0.100" = { git = "https://github.com/me/serde", branch = "fix" }
+"https://github.com/example/index#bar@0.2.0" = { path = "../bar" }
+
+[patch.crates-io]
+baz = { path = "../baz" }
+"#;
+    let mut m = Manifest::from_str(toml).unwrap();
+    let replace = m.replace.as_ref().unwrap();
+    let serde: lib::PackageIdSpec = "serde:1.0.100".parse().unwrap();
+    assert_eq!("serde", serde.name);
+    assert_eq!(Some("1.0.100"), serde.version.as_deref());
+    assert_eq!("serde@1.0.100", serde.to_string());
+    assert_eq!(Some("https://github.com/me/serde"), replace[&serde].git());
+    let bar = replace.keys().find(|spec| spec.name == "bar").unwrap();
+    assert_eq!(Some("https://github.com/example/index"), bar.url.as_deref());
+
+    let round_trip = Manifest::from_str(&toml::to_string(&m).unwrap()).unwrap();
+    assert_eq!(m.replace, round_trip.replace);
+    // Keys are written back as they were, not in their canonical form
+    let replace_table = &toml::Value::try_from(&m).unwrap()["replace"];
+    assert!(replace_table.get("serde:1.0.100").is_some());
+    assert_eq!(
+        serde,
+        "serde@1.0.100".parse::<lib::PackageIdSpec>().unwrap()
+    );
+    // A changed spec is written from its fields
+    let mut bumped = m.clone();
+    let mut replace = bumped.replace.take().unwrap();
+    let dep = replace.remove(&serde).unwrap();
+    let mut spec = serde.clone();
+    spec.version = Some("2.0.0".into());
+    replace.insert(spec, dep);
+    bumped.replace = Some(replace);
+    let replace_table = &toml::Value::try_from(&bumped).unwrap()["replace"];
+    assert!(replace_table.get("serde@2.0.0").is_some());
+    assert!(replace_table.get("serde:1.0.100").is_none());
+
+    m.migrate_replace_to_patch().unwrap();
+    assert!(m.replace.is_none());
+    let patch = m.patch.as_ref().unwrap();
+    assert_eq!(2, patch["crates-io"].len());
+    assert_eq!(
+        Some("https://github.com/me/serde"),
+        patch["crates-io"]["serde"].git()
+    );
+    assert!(patch["https://github.com/example/index"].contains_key("bar"));
+
+    let conflict = toml.replace("baz = ", "serde = ");
+    let mut m = Manifest::from_str(&conflict).unwrap();
+    let err = m.migrate_replace_to_patch().unwrap_err().to_string();
+    assert!(err.contains("`replace.\"serde:1.0.100\"`"), "{}", err);
+    assert!(m.replace.is_some());
+
+    let mut m =
+        Manifest::from_str("[package]\nname = \"foo\"\nversion = \"1\"\n[replace]\n").unwrap();
+    m.migrate_replace_to_patch().unwrap();
+    assert!(m.replace.is_none());
+    assert!(m.patch.is_none());
+
+    let invalid = toml.replace("serde:1.0.100", "serde:");
+    assert!(Manifest::from_str(&invalid).is_err());
+}
+
+#[test]
+fn package_id_spec() {
+    use lib::{GitReference, PackageIdSpec, SourceKind};
+
+    let parse = |s: &str| s.parse::<PackageIdSpec>().unwrap();
+
+    let spec = parse("serde");
+    assert_eq!(
+        ("serde", None, None),
+        (spec.name.as_str(), spec.version, spec.url)
+    );
+
+    let spec = parse("https://github.com/rust-lang/crates.io-index#regex@1.4.3");
+    assert_eq!("regex", spec.name);
+    assert_eq!(Some("1.4.3"), spec.version.as_deref());
+    assert_eq!(
+        Some("https://github.com/rust-lang/crates.io-index"),
+        spec.url.as_deref()
+    );
+    assert_eq!(None, spec.kind);
+
+    let spec = parse("https://github.com/rust-lang/cargo#0.52.0");
+    assert_eq!("cargo", spec.name);
+    assert_eq!(Some("0.52.0"), spec.version.as_deref());
+
+    let spec = parse("git+https://github.com/rust-lang/cargo?branch=master#cargo@0.52.0");
+    assert_eq!(
+        Some(SourceKind::Git(GitReference::Branch("master".into()))),
+        spec.kind
+    );
+    assert_eq!(
+        Some("https://github.com/rust-lang/cargo"),
+        spec.url.as_deref()
+    );
+
+    let spec = parse("path+file:///path/to/my/project/foo");
+    assert_eq!("foo", spec.name);
+    assert_eq!(Some(SourceKind::Path), spec.kind);
+    assert_eq!(None, spec.version);
+
+    let spec = parse("path+file:///x#foo@0.1.0");
+    assert_eq!("foo", spec.name);
+    assert_eq!(Some("file:///x"), spec.url.as_deref());
+
+    // Display gives the canonical form, which parses to the same spec
+    for s in [
+        "serde@1.0",
+        "https://github.com/rust-lang/cargo#0.52.0",
+        "git+https://github.com/rust-lang/cargo?tag=v1#cargo-util@0.2.0",
+        "sparse+https://index.crates.io/#serde",
+        "path+file:///x#foo@0.1.0",
+    ] {
+        assert_eq!(s, parse(s).to_string());
+    }
+    assert_eq!("serde@1.0.100", parse("serde:1.0.100").to_string());
+
+    for invalid in [
+        "",
+        "serde@",
+        "serde@1.x",
+        "serde@1.0-beta",
+        "foo bar",
+        "https://example.com/",
+        "ftp+https://example.com/foo",
+        "https://example.com/foo?branch=main",
+    ] {
+        assert!(invalid.parse::<PackageIdSpec>().is_err(), "{}", invalid);
+    }
+
+    let spec = parse("https://github.com/rust-lang/crates.io-index#regex@1.4");
+    let index = "registry+https://github.com/rust-lang/crates.io-index";
+    assert!(spec.matches("regex", "1.4.3", Some(index)));
+    assert!(spec.matches("regex", "1.4.3", None));
+    assert!(!spec.matches("regex", "1.40.0", Some(index)));
+    assert!(!spec.matches("regex", "1.4.3", Some("https://example.com/index")));
+    assert!(!spec.matches("regex-syntax", "1.4.3", Some(index)));
+    assert!(parse("regex").matches("regex", "0.1.0", Some(index)));
+    assert!(parse("regex@1.4.3-beta").matches("regex", "1.4.3-beta", None));
+}
+
+#[test]
+fn autotargets() {
+    let products = |products: &Option<Vec<lib::Product>>| -> Vec<(String, String)> {
+        products
+            .iter()
+            .flatten()
+            .map(|p| (p.name.clone().unwrap(), p.path.clone().unwrap()))
+            .collect()
+    };
+    let pair = |name: &str, path: &str| (name.to_string(), path.to_string());
+
+    let m = Manifest::from_path("tests/autotargets/Cargo.toml").unwrap();
+    // A partially specified `[lib]` gets its name and path inferred
+    let lib = m.lib.as_ref().unwrap();
+    assert_eq!(Some("auto_targets"), lib.name.as_deref());
+    assert_eq!(Some("src/lib.rs"), lib.path.as_deref());
+    assert_eq!(Some(vec!["cdylib".to_string()]), lib.crate_type);
+
+    // Declared products come first, then discovered ones that aren't declared
+    assert_eq!(
+        vec![
+            pair("cli", "src/bin/cli/main.rs"),
+            pair("tool", "src/bin/tool.rs"),
+            pair("auto-targets", "src/main.rs"),
+            pair("extra", "src/bin/extra.rs"),
+        ],
+        products(&m.bin)
+    );
+    let bins = m.bin.as_ref().unwrap();
+    assert_eq!(vec!["tool".to_string()], bins[1].required_features);
+    assert_eq!(None, bins[1].edition);
+    assert_eq!(Some(lib::Edition::E2021), bins[3].edition);
+    assert_eq!(
+        vec![
+            pair("demo", "examples/demo.rs"),
+            pair("multi", "examples/multi/main.rs")
+        ],
+        products(&m.example)
+    );
+    assert_eq!(vec![pair("it", "tests/it.rs")], products(&m.test));
+    assert_eq!(vec![pair("speed", "benches/speed.rs")], products(&m.bench));
+
+    let toml = std::fs::read_to_string("tests/autotargets/Cargo.toml").unwrap();
+    let toml = toml.replace(
+        "edition = \"2021\"",
+        "edition = \"2021\"\nautolib = false\nautobins = false\nautoexamples = false",
+    );
+    let toml = toml.replace("[lib]\ncrate-type = [\"cdylib\"]\n", "");
+    let mut m = Manifest::from_str(&toml).unwrap();
+    m.complete_from_path(std::path::Path::new("tests/autotargets/Cargo.toml"))
+        .unwrap();
+    assert!(m.lib.is_none());
+    assert_eq!(
+        vec![
+            pair("cli", "src/bin/cli/main.rs"),
+            pair("tool", "src/bin/tool.rs")
+        ],
+        products(&m.bin)
+    );
+    assert_eq!(vec![pair("demo", "examples/demo.rs")], products(&m.example));
+    assert_eq!(vec![pair("it", "tests/it.rs")], products(&m.test));
+}
+
+#[test]
+fn inference_warnings() {
+    use lib::{InferenceWarning, ProductKind};
+
+    let path = std::path::Path::new("tests/ambiguous/Cargo.toml");
+    let mut m = Manifest::from_slice(&read(path).unwrap()).unwrap();
+    let warnings = m.complete_from_path(path).unwrap();
+    assert_eq!(
+        vec![
+            InferenceWarning::InvalidName {
+                kind: ProductKind::Bin,
+                name: "2fast".into(),
+                path: "src/bin/2fast.rs".into()
+            },
+            InferenceWarning::AmbiguousFiles {
+                kind: ProductKind::Bin,
+                name: "foo".into(),
+                paths: vec!["src/bin/foo/main.rs".into(), "src/bin/foo.rs".into()]
+            },
+            InferenceWarning::Shadowed {
+                kind: ProductKind::Bin,
+                name: "tool".into(),
+                declared_path: "src/tool.rs".into(),
+                discovered_path: "src/bin/tool.rs".into()
+            },
+            InferenceWarning::UnnamedMain {
+                kind: ProductKind::Example,
+                path: "examples/thing/main.rs".into(),
+                name: "thing".into()
+            },
+        ],
+        warnings
+    );
+    assert_eq!(
+        "found more than one file for bin `foo`: src/bin/foo/main.rs, src/bin/foo.rs; \
+         using `src/bin/foo/main.rs`",
+        warnings[1].to_string()
+    );
+    let empty = InferenceWarning::AmbiguousFiles {
+        kind: ProductKind::Bin,
+        name: "foo".into(),
+        paths: vec![],
+    };
+    assert_eq!(
+        "found more than one file for bin `foo`: ",
+        empty.to_string()
+    );
+
+    // Each product is only added once
+    let bins: Vec<_> = m
+        .bin
+        .iter()
+        .flatten()
+        .map(|bin| bin.name.as_deref().unwrap())
+        .collect();
+    assert_eq!(vec!["tool", "foo"], bins);
+    assert_eq!(Vec::<lib::Diagnostic>::new(), m.validate());
+}
+
+/// Products are discovered in a `MemoryFilesystem` like on disk.
+#[test]
+fn memory_filesystem_autodiscovery() {
+    let fs = lib::MemoryFilesystem::new()
+        .with_file(
+            "Cargo.toml",
+            "[package]\nname = \"mem\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        )
+        .with_file("src/lib.rs", "")
+        .with_file("./src/main.rs", "fn main() {}")
+        .with_file("src/bin/cli/main.rs", "fn main() {}")
+        .with_file("src/bin/cli/args.rs", "")
+        .with_file("examples/demo.rs", "fn main() {}")
+        .with_file("build.rs", "fn main() {}");
+    assert!(fs.is_dir("src/bin"));
+    assert!(fs.exists("src/bin/cli"));
+    assert!(!fs.is_dir("src/lib.rs"));
+    assert!(!fs.exists("tests"));
+    assert_eq!(
+        std::io::ErrorKind::InvalidInput,
+        fs.file_names_in("src/lib.rs").unwrap_err().kind()
+    );
+    assert_eq!(
+        std::io::ErrorKind::NotFound,
+        fs.file_names_in("benches").unwrap_err().kind()
+    );
+    // `..` can't leave the filesystem
+    assert!(fs.exists("src/../build.rs"));
+    assert!(!fs.exists("../build.rs"));
+    assert!(!fs.is_dir("src/../.."));
+    assert_eq!(
+        std::io::ErrorKind::NotFound,
+        fs.read_file("../build.rs").unwrap_err().kind()
+    );
+    let mut outside = fs.clone();
+    assert_eq!(
+        std::io::ErrorKind::InvalidInput,
+        outside.try_insert("../build.rs", "").unwrap_err().kind()
+    );
+    assert_eq!(fs, outside);
+    let files: std::collections::BTreeMap<std::path::PathBuf, Vec<u8>> =
+        [("/etc/passwd".into(), Vec::new())].into_iter().collect();
+    assert_eq!(
+        std::io::ErrorKind::InvalidInput,
+        lib::MemoryFilesystem::try_from(files).unwrap_err().kind()
+    );
+
+    let m = Manifest::from_abstract_filesystem(&fs).unwrap();
+    assert_eq!(Some("mem"), m.lib.unwrap().name.as_deref());
+    let bins: Vec<_> = m
+        .bin
+        .iter()
+        .flatten()
+        .map(|bin| (bin.name.as_deref().unwrap(), bin.path.as_deref().unwrap()))
+        .collect();
+    assert_eq!(
+        vec![("mem", "src/main.rs"), ("cli", "src/bin/cli/main.rs")],
+        bins
+    );
+    assert_eq!(
+        Some("examples/demo.rs"),
+        m.example.unwrap()[0].path.as_deref()
+    );
+    assert!(m.test.unwrap_or_default().is_empty());
+    assert_eq!(
+        Some(&toml::Value::String("build.rs".into())),
+        m.package.unwrap().build.as_ref()
+    );
+}
+
+/// Workspaces can be loaded from a `MemoryFilesystem` built from a map of files.
+#[test]
+fn memory_filesystem_workspace() {
+    let files: std::collections::BTreeMap<std::path::PathBuf, Vec<u8>> = [
+        (
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\n[workspace.package]\nversion = \"2.0.0\"\n",
+        ),
+        (
+            "crates/a/Cargo.toml",
+            "[package]\nname = \"a\"\nversion.workspace = true\n",
+        ),
+        ("crates/a/src/lib.rs", ""),
+        (
+            "crates/b/Cargo.toml",
+            "[package]\nname = \"b\"\nversion = \"0.1.0\"\n",
+        ),
+        ("crates/b/src/main.rs", "fn main() {}"),
+        ("crates/notes/README.md", ""),
+    ]
+    .into_iter()
+    .map(|(path, contents)| (path.into(), contents.into()))
+    .collect();
+    let fs = lib::MemoryFilesystem::try_from(files).unwrap();
+
+    let root = Manifest::from_abstract_filesystem(&fs).unwrap();
+    let members = root.workspace.unwrap().discover_members(&fs).unwrap();
+    assert_eq!(
+        vec!["crates/a", "crates/b"],
+        members.keys().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        MaybeInherited::Local("2.0.0".to_string()),
+        members["crates/a"].package.as_ref().unwrap().version
+    );
+    assert_eq!(
+        Some("b"),
+        members["crates/b"].bin.as_ref().unwrap()[0].name.as_deref()
+    );
+
+    let a = Manifest::from_abstract_filesystem_resolved(&fs, "crates/a").unwrap();
+    assert_eq!(
+        MaybeInherited::Local("2.0.0".to_string()),
+        a.package.unwrap().version
+    );
+    assert_eq!(Some("src/lib.rs"), a.lib.unwrap().path.as_deref());
+}
+
+/// A `.crate` archive is read without its `name-version/` directory.
+#[cfg(feature = "tarball")]
+#[test]
+fn crate_file() {
+    use flate2::write::GzEncoder;
+
+    let files = [
+        (
+            "demo-0.1.0/Cargo.toml",
+            "[package]\nname = \"demo\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        ),
+        ("demo-0.1.0/Cargo.toml.orig", "[package]\nname = \"demo\"\n"),
+        ("demo-0.1.0/src/lib.rs", ""),
+        ("demo-0.1.0/src/bin/tool.rs", "fn main() {}"),
+    ];
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Default::default()));
+    for (path, contents) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, path, contents.as_bytes())
+            .unwrap();
+    }
+    let gzipped = builder.into_inner().unwrap().finish().unwrap();
+
+    let fs = lib::TarballFilesystem::new(&gzipped[..]).unwrap();
+    assert_eq!("demo-0.1.0", fs.prefix());
+    assert!(fs.exists("Cargo.toml.orig"));
+    assert!(fs.is_dir("src/bin"));
+    assert_eq!(
+        b"fn main() {}".to_vec(),
+        fs.read_file("src/bin/tool.rs").unwrap()
+    );
+
+    let path = std::env::temp_dir().join(format!("demo-0.1.0-{}.crate", std::process::id()));
+    std::fs::write(&path, &gzipped).unwrap();
+    let m = Manifest::from_crate_file(&path);
+    std::fs::remove_file(&path).unwrap();
+    let m = m.unwrap();
+    assert_eq!(Some("demo"), m.lib.unwrap().name.as_deref());
+    assert_eq!(Some("src/bin/tool.rs"), m.bin.unwrap()[0].path.as_deref());
+
+    // Files outside of the `name-version/` directory are rejected
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Default::default()));
+    for path in ["a-1.0.0/Cargo.toml", "b-1.0.0/Cargo.toml"] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(0);
+        header.set_cksum();
+        builder.append_data(&mut header, path, &[][..]).unwrap();
+    }
+    let gzipped = builder.into_inner().unwrap().finish().unwrap();
+    let err = lib::TarballFilesystem::new(&gzipped[..]).unwrap_err();
+    assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+}